@@ -1,47 +1,316 @@
-use autocxx::{prelude::*, subclass::subclass, moveit::MakeCppStorage};
+//! Bindings for the Morfeusz2 morphological analyser. `Morfeusz` is the
+//! safe wrapper, the raw autocxx bindings stay private to this crate.
+
+use autocxx::prelude::*;
 
 include_cpp! {
     #include "morfeusz2.h"
+    #include "shim.h"
     safety!(unsafe_ffi)
     generate_ns!("morfeusz")
+    generate_ns!("morfeusz_shim")
+}
+
+use std::fmt;
+
+use ffi::{morfeusz, morfeusz_shim};
+
+pub type ErrBox = Box<dyn std::error::Error>;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Usage {
+    AnalyseOnly,
+    GenerateOnly,
+    #[default]
+    AnalyseAndGenerate,
+}
+
+impl From<Usage> for morfeusz::MorfeuszUsage {
+    fn from(usage: Usage) -> Self {
+        match usage {
+            Usage::AnalyseOnly => Self::ANALYSE_ONLY,
+            Usage::GenerateOnly => Self::GENERATE_ONLY,
+            Usage::AnalyseAndGenerate => Self::BOTH_ANALYSE_AND_GENERATE,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CaseHandling {
+    /// Prefer interpretations matching the input case, fall back to
+    /// the others
+    #[default]
+    ConditionallyCaseSensitive,
+    StrictlyCaseSensitive,
+    IgnoreCase,
+}
+
+impl From<CaseHandling> for morfeusz::CaseHandling {
+    fn from(case_handling: CaseHandling) -> Self {
+        match case_handling {
+            CaseHandling::ConditionallyCaseSensitive => Self::CONDITIONALLY_CASE_SENSITIVE,
+            CaseHandling::StrictlyCaseSensitive => Self::STRICTLY_CASE_SENSITIVE,
+            CaseHandling::IgnoreCase => Self::IGNORE_CASE,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WhitespaceHandling {
+    /// Whitespace isn't reported at all
+    #[default]
+    Skip,
+    /// Whitespace is appended to the preceding token's orth
+    Append,
+    /// Whitespace is reported as separate interpretations
+    Keep,
+}
+
+impl From<WhitespaceHandling> for morfeusz::WhitespaceHandling {
+    fn from(whitespace_handling: WhitespaceHandling) -> Self {
+        match whitespace_handling {
+            WhitespaceHandling::Skip => Self::SKIP_WHITESPACES,
+            WhitespaceHandling::Append => Self::APPEND_WHITESPACES,
+            WhitespaceHandling::Keep => Self::KEEP_WHITESPACES,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Settings {
+    pub usage: Usage,
+    /// Dictionary name, Morfeusz's default dictionary if None
+    pub dictionary: Option<String>,
+    pub case_handling: CaseHandling,
+    pub whitespace_handling: WhitespaceHandling,
+}
+
+/// One analysis of a text segment. Segments are edges in a graph whose
+/// nodes are positions in the text, an ambiguous text yields several
+/// paths through it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Interpretation {
+    pub start_node: i32,
+    pub end_node: i32,
+    pub orth: String,
+    pub lemma: String,
+    /// Full morphosyntactic tag, e.g. "subst:sg:nom:m2"
+    pub tag: String,
+    /// Proper name classification, usually empty
+    pub name: String,
+    /// Qualifiers such as "pot." or "daw."
+    pub labels: Vec<String>,
+    /// Unknown to the dictionary
+    pub is_ign: bool,
+    pub is_whitespace: bool,
+}
+
+impl Interpretation {
+    /// The part of speech, i.e. the first section of the tag
+    pub fn pos(&self) -> &str {
+        self.tag.split(':').next().unwrap_or_default()
+    }
+}
+
+/// One generated form of a lemma
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Form {
+    pub orth: String,
+    pub lemma: String,
+    pub tag: String,
+    pub name: String,
+    pub labels: Vec<String>,
+}
+
+impl From<Interpretation> for Form {
+    fn from(i: Interpretation) -> Self {
+        Self {
+            orth: i.orth,
+            lemma: i.lemma,
+            tag: i.tag,
+            name: i.name,
+            labels: i.labels,
+        }
+    }
+}
+
+/// A Morfeusz instance. Not thread-safe, create one per thread.
+pub struct Morfeusz {
+    inner: UniquePtr<morfeusz::Morfeusz>,
+    settings: Settings,
 }
 
+impl Morfeusz {
+    pub fn new(settings: Settings) -> Result<Self, ErrBox> {
+        let inner = match &settings.dictionary {
+            Some(dict_name) => {
+                cxx::let_cxx_string!(dict_name_cxx = dict_name);
+                morfeusz_shim::create_instance_with_dict(&dict_name_cxx, settings.usage.into())
+            }
+            None => morfeusz_shim::create_instance(settings.usage.into()),
+        };
+
+        if inner.is_null() {
+            return Err(format!("Could not create Morfeusz instance for {:?}", settings).into());
+        }
 
-use std::pin::Pin;
+        let mut ret = Self {
+            inner,
+            settings: Settings::default(),
+        };
 
-pub use ffi::morfeusz;
+        ret.set_case_handling(settings.case_handling);
+        ret.set_whitespace_handling(settings.whitespace_handling);
+        ret.settings = settings;
+
+        Ok(ret)
+    }
+
+    pub fn version() -> String {
+        morfeusz_shim::version().to_string_lossy().into_owned()
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn set_case_handling(&mut self, case_handling: CaseHandling) {
+        morfeusz_shim::set_case_handling(self.inner.pin_mut(), case_handling.into());
+        self.settings.case_handling = case_handling;
+    }
+
+    pub fn set_whitespace_handling(&mut self, whitespace_handling: WhitespaceHandling) {
+        morfeusz_shim::set_whitespace_handling(self.inner.pin_mut(), whitespace_handling.into());
+        self.settings.whitespace_handling = whitespace_handling;
+    }
+
+    /// All interpretations of every segment of text. Needs an
+    /// analysing instance.
+    pub fn analyse(&self, text: &str) -> Result<Vec<Interpretation>, ErrBox> {
+        if self.settings.usage == Usage::GenerateOnly {
+            return Err("Morfeusz instance was created for generation only".into());
+        }
+
+        cxx::let_cxx_string!(text_cxx = text);
+        let results = morfeusz_shim::analyse(self.handle(), &text_cxx);
+
+        Ok(self.collect(&results))
+    }
+
+    /// Every form of lemma. Needs a generating instance.
+    pub fn generate(&self, lemma: &str) -> Result<Vec<Form>, ErrBox> {
+        if self.settings.usage == Usage::AnalyseOnly {
+            return Err("Morfeusz instance was created for analysis only".into());
+        }
+
+        cxx::let_cxx_string!(lemma_cxx = lemma);
+        let results = morfeusz_shim::generate(self.handle(), &lemma_cxx);
+
+        Ok(self.collect(&results).into_iter().map(Form::from).collect())
+    }
+
+    fn handle(&self) -> &morfeusz::Morfeusz {
+        self.inner
+            .as_ref()
+            .expect("Morfeusz::new() rejects null instances")
+    }
+
+    fn collect(&self, results: &UniquePtr<morfeusz_shim::Interpretations>) -> Vec<Interpretation> {
+        let Some(results) = results.as_ref() else {
+            return vec![];
+        };
+
+        (0..results.size())
+            .map(|idx| self.interpretation(results.at(idx)))
+            .collect()
+    }
+
+    /// Copy out an interpretation, resolving its ids to strings
+    fn interpretation(&self, i: &morfeusz::MorphInterpretation) -> Interpretation {
+        let handle = self.handle();
+        let labels = morfeusz_shim::labels_as_string(handle, morfeusz_shim::labels_id(i))
+            .to_string_lossy()
+            .split('|')
+            .filter(|label| !label.is_empty())
+            .map(|label| label.to_owned())
+            .collect();
+
+        Interpretation {
+            start_node: morfeusz_shim::start_node(i).0,
+            end_node: morfeusz_shim::end_node(i).0,
+            orth: morfeusz_shim::orth(i).to_string_lossy().into_owned(),
+            lemma: morfeusz_shim::lemma(i).to_string_lossy().into_owned(),
+            tag: morfeusz_shim::tag(handle, morfeusz_shim::tag_id(i))
+                .to_string_lossy()
+                .into_owned(),
+            name: morfeusz_shim::name(handle, morfeusz_shim::name_id(i))
+                .to_string_lossy()
+                .into_owned(),
+            labels,
+            is_ign: morfeusz_shim::is_ign(i),
+            is_whitespace: morfeusz_shim::is_whitespace(i),
+        }
+    }
+}
+
+impl fmt::Debug for Morfeusz {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Morfeusz")
+            .field("settings", &self.settings)
+            .finish()
+    }
+}
 
 #[cfg(test)]
 pub mod test {
     use super::*;
 
     #[test]
-    fn test_sanity() {
-        let morf =
-            morfeusz::Morfeusz::createInstance(morfeusz::MorfeuszUsage::BOTH_ANALYSE_AND_GENERATE);
+    fn test_whitespace_and_unknown_words() -> Result<(), ErrBox> {
+        let mut morf = Morfeusz::new(Settings {
+            whitespace_handling: WhitespaceHandling::Keep,
+            ..Default::default()
+        })?;
 
-        let morf_ref = unsafe {
-            morf.as_ref()
-                .expect("Could not turn Morfeusz struct into reference")
-        };
+        let interps = morf.analyse("kotek xqzwt")?;
+        assert!(interps.iter().any(|i| i.is_whitespace));
+        assert!(interps.iter().any(|i| i.orth == "xqzwt" && i.is_ign));
+        assert!(interps.iter().all(|i| i.orth != "kotek" || !i.is_ign));
 
-        cxx::let_cxx_string!(input = "wlazł kotek na płotek");
-        let mut result_ptr = unsafe {UniquePtr::from_raw(morf_ref.analyse(&input))};
+        morf.set_whitespace_handling(WhitespaceHandling::Skip);
+        assert_eq!(morf.settings().whitespace_handling, WhitespaceHandling::Skip);
+        assert!(morf.analyse("kotek xqzwt")?.iter().all(|i| !i.is_whitespace));
 
-        unsafe {
-	    while result_ptr.pin_mut().hasNext() {
-		println!("Iteratin' this boiii");
-		let next = result_ptr.pin_mut().next().within_unique_ptr();
+        Ok(())
+    }
 
-		println!("isIgn: {}", next.isIgn());
-		println!("isWhitespace: {}", next.isWhitespace());
-            }
+    #[test]
+    fn test_analyse_and_generate() -> Result<(), ErrBox> {
+        let morf = Morfeusz::new(Settings::default())?;
+
+        let interps = morf.analyse("wlazł kotek na płotek")?;
+
+        assert!(interps
+            .iter()
+            .any(|i| i.orth == "kotek" && i.lemma == "kotek" && i.pos() == "subst"));
+        assert!(interps.iter().all(|i| !i.is_whitespace));
+
+        let forms = morf.generate("kotek")?;
 
-	    let resolver = morf_ref.getIdResolver();
+        assert!(forms.iter().any(|f| f.orth == "kotkami"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_usage_is_enforced() -> Result<(), ErrBox> {
+        let morf = Morfeusz::new(Settings {
+            usage: Usage::AnalyseOnly,
+            ..Default::default()
+        })?;
 
-	    let tag_cnt = resolver.getTagsCount();
+        assert!(morf.generate("kotek").is_err());
 
-	    println!("Got {} tags", tag_cnt);
-	}
+        Ok(())
     }
 }
@@ -0,0 +1,117 @@
+// Thin helpers over morfeusz2.h for the parts autocxx can't bind
+// directly: raw owning pointers, std::vector out-parameters and public
+// fields of MorphInterpretation.
+
+#pragma once
+
+#include <memory>
+#include <string>
+#include <vector>
+
+#include "morfeusz2.h"
+
+namespace morfeusz_shim {
+
+inline std::unique_ptr<morfeusz::Morfeusz>
+create_instance(morfeusz::MorfeuszUsage usage) {
+  return std::unique_ptr<morfeusz::Morfeusz>(
+      morfeusz::Morfeusz::createInstance(usage));
+}
+
+inline std::unique_ptr<morfeusz::Morfeusz>
+create_instance_with_dict(const std::string &dict_name,
+                          morfeusz::MorfeuszUsage usage) {
+  return std::unique_ptr<morfeusz::Morfeusz>(
+      morfeusz::Morfeusz::createInstance(dict_name, usage));
+}
+
+inline std::unique_ptr<std::string> version() {
+  return std::make_unique<std::string>(morfeusz::Morfeusz::getVersion());
+}
+
+class Interpretations {
+public:
+  size_t size() const { return items.size(); }
+  const morfeusz::MorphInterpretation &at(size_t idx) const {
+    return items.at(idx);
+  }
+
+  std::vector<morfeusz::MorphInterpretation> items;
+};
+
+inline std::unique_ptr<Interpretations> analyse(const morfeusz::Morfeusz &m,
+                                                const std::string &text) {
+  auto ret = std::make_unique<Interpretations>();
+  m.analyse(text, ret->items);
+  return ret;
+}
+
+inline std::unique_ptr<Interpretations> generate(const morfeusz::Morfeusz &m,
+                                                 const std::string &lemma) {
+  auto ret = std::make_unique<Interpretations>();
+  m.generate(lemma, ret->items);
+  return ret;
+}
+
+inline int start_node(const morfeusz::MorphInterpretation &i) {
+  return i.startNode;
+}
+
+inline int end_node(const morfeusz::MorphInterpretation &i) {
+  return i.endNode;
+}
+
+inline std::unique_ptr<std::string>
+orth(const morfeusz::MorphInterpretation &i) {
+  return std::make_unique<std::string>(i.orth);
+}
+
+inline std::unique_ptr<std::string>
+lemma(const morfeusz::MorphInterpretation &i) {
+  return std::make_unique<std::string>(i.lemma);
+}
+
+inline int tag_id(const morfeusz::MorphInterpretation &i) { return i.tagId; }
+
+inline int name_id(const morfeusz::MorphInterpretation &i) { return i.nameId; }
+
+inline int labels_id(const morfeusz::MorphInterpretation &i) {
+  return i.labelsId;
+}
+
+inline bool is_ign(const morfeusz::MorphInterpretation &i) {
+  return i.isIgn();
+}
+
+inline bool is_whitespace(const morfeusz::MorphInterpretation &i) {
+  return i.isWhitespace();
+}
+
+inline std::unique_ptr<std::string> tag(const morfeusz::Morfeusz &m,
+                                        int tag_id) {
+  return std::make_unique<std::string>(m.getIdResolver().getTag(tag_id));
+}
+
+inline std::unique_ptr<std::string> name(const morfeusz::Morfeusz &m,
+                                         int name_id) {
+  return std::make_unique<std::string>(m.getIdResolver().getName(name_id));
+}
+
+inline std::unique_ptr<std::string> labels_as_string(const morfeusz::Morfeusz &m,
+                                                     int labels_id) {
+  return std::make_unique<std::string>(
+      m.getIdResolver().getLabelsAsString(labels_id));
+}
+
+inline void set_case_handling(morfeusz::Morfeusz &m,
+                              morfeusz::CaseHandling case_handling) {
+  m.setCaseHandling(case_handling);
+}
+
+inline void
+set_whitespace_handling(morfeusz::Morfeusz &m,
+                        morfeusz::WhitespaceHandling whitespace_handling) {
+  m.setWhitespaceHandling(whitespace_handling);
+}
+
+} // namespace morfeusz_shim
@@ -1,127 +1,124 @@
-use std::{fmt::Debug, str::FromStr, cmp::Ordering};
+use lazy_static::lazy_static;
 
-use crate::ErrBox;
+use std::{cmp::Ordering, fmt::Debug, str::FromStr};
 
-/// Middle keys and hyphen - They help us disambiguate left/right keys
-const MID_CHARACTERS: &'static str = "JE~*IAU-";
+use crate::ErrBox;
 
 const INVALID_CHORDS: &'static [&'static str] = &["XS", "FZ", "L*C", "R~R", "-TY", "-WO", "JIU"];
 
-#[derive(Default, Clone, Debug, PartialEq, Eq, Ord, PartialOrd)]
-pub struct Chord {
-    x: bool,
-    f: bool,
-    z: bool,
-    s_left: bool,
-    k: bool,
-    t_left: bool,
-    p: bool,
-    v: bool,
-    l_left: bool,
-    r_left: bool,
-    j: bool,
-    e: bool,
-    tilde: bool,
-    asterisk: bool,
-    i: bool,
-    a: bool,
-    u: bool,
-    c: bool,
-    r_right: bool,
-    l_right: bool,
-    b: bool,
-    s_right: bool,
-    g: bool,
-    t_right: bool,
-    w: bool,
-    o: bool,
-    y: bool,
+/// Which part of the keyboard a key belongs to. Mid keys help us
+/// disambiguate left/right keys sharing the same letter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Mid,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Key {
+    pub letter: char,
+    pub side: Side,
+}
+
+impl Key {
+    const fn new(letter: char, side: Side) -> Self {
+        Self { letter, side }
+    }
+}
+
+/// Every key of the layout in steno order
+pub const KEYS: [Key; 27] = [
+    Key::new('X', Side::Left),
+    Key::new('F', Side::Left),
+    Key::new('Z', Side::Left),
+    Key::new('S', Side::Left),
+    Key::new('K', Side::Left),
+    Key::new('T', Side::Left),
+    Key::new('P', Side::Left),
+    Key::new('V', Side::Left),
+    Key::new('L', Side::Left),
+    Key::new('R', Side::Left),
+    Key::new('J', Side::Mid),
+    Key::new('E', Side::Mid),
+    Key::new('~', Side::Mid),
+    Key::new('*', Side::Mid),
+    Key::new('I', Side::Mid),
+    Key::new('A', Side::Mid),
+    Key::new('U', Side::Mid),
+    Key::new('C', Side::Right),
+    Key::new('R', Side::Right),
+    Key::new('L', Side::Right),
+    Key::new('B', Side::Right),
+    Key::new('S', Side::Right),
+    Key::new('G', Side::Right),
+    Key::new('T', Side::Right),
+    Key::new('W', Side::Right),
+    Key::new('O', Side::Right),
+    Key::new('Y', Side::Right),
+];
+
+lazy_static! {
+    static ref INVALID_CHORD_MASKS: Vec<Chord> = INVALID_CHORDS
+        .iter()
+        .map(|s| s.parse().expect("INVALID_CHORDS must parse"))
+        .collect();
 }
 
+/// A set of pressed keys, stored as a bitmask. The first key in steno
+/// order occupies the most significant bit, which keeps the numeric
+/// ordering identical to comparing keys one by one in steno order.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
+pub struct Chord(u32);
+
 impl Chord {
-    pub fn as_vec(&self) -> Vec<&bool> {
-        // Will fail the build if the struct schema is changed without updating this assignment
-        let Self {
-            x,
-            f,
-            z,
-            s_left,
-            k,
-            t_left,
-            p,
-            v,
-            l_left,
-            r_left,
-            j,
-            e,
-            tilde,
-            asterisk,
-            i,
-            a,
-            u,
-            c,
-            r_right,
-            l_right,
-            b,
-            s_right,
-            g,
-            t_right,
-            w,
-            o,
-            y,
-        } = self;
-
-        vec![
-            x, f, z, s_left, k, t_left, p, v, l_left, r_left, j, e, tilde, asterisk, i, a, u, c,
-            r_right, l_right, b, s_right, g, t_right, w, o, y,
-        ]
-    }
-
-    pub fn as_mut_vec(&mut self) -> Vec<&mut bool> {
-        // Will fail the build if the struct schema is changed without updating this assignment
-        let Self {
-            x,
-            f,
-            z,
-            s_left,
-            k,
-            t_left,
-            p,
-            v,
-            l_left,
-            r_left,
-            j,
-            e,
-            tilde,
-            asterisk,
-            i,
-            a,
-            u,
-            c,
-            r_right,
-            l_right,
-            b,
-            s_right,
-            g,
-            t_right,
-            w,
-            o,
-            y,
-        } = self;
-        vec![
-            x, f, z, s_left, k, t_left, p, v, l_left, r_left, j, e, tilde, asterisk, i, a, u, c,
-            r_right, l_right, b, s_right, g, t_right, w, o, y,
-        ]
+    const fn key_bit(idx: usize) -> u32 {
+        1 << (KEYS.len() - 1 - idx)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn key_count(&self) -> u32 {
+        self.0.count_ones()
+    }
+
+    pub fn has_key(&self, idx: usize) -> bool {
+        self.0 & Self::key_bit(idx) != 0
+    }
+
+    /// Indices into `KEYS` of all pressed keys, in steno order
+    pub fn key_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..KEYS.len()).filter(move |idx| self.has_key(*idx))
+    }
+
+    /// All pressed keys, in steno order
+    pub fn keys(&self) -> impl Iterator<Item = &'static Key> + '_ {
+        self.key_indices().map(|idx| &KEYS[idx])
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    /// True if any key is pressed in both chords
+    pub fn conflicts_with(&self, other: &Self) -> bool {
+        !self.intersection(other).is_empty()
+    }
+
+    /// True if every key of self is also pressed in other
+    pub fn is_subset_of(&self, other: &Self) -> bool {
+        self.0 & !other.0 == 0
     }
 
     /// Sum this chord with another, failing if an already pressed key is pressed in other
     pub fn merge(&mut self, other: &Chord) -> Result<(), ErrBox> {
-        if self
-            .as_vec()
-            .into_iter()
-            .zip(other.as_vec().into_iter())
-            .any(|(self_key, other_key)| *self_key && *other_key)
-        {
+        if self.conflicts_with(other) {
             return Err(format!(
                 "Duplicate keys between {} and {}",
                 self.to_string(),
@@ -130,16 +127,7 @@ impl Chord {
             .into());
         }
 
-        let mut new = Self::default();
-
-        for ((self_key, other_key), new_key) in self
-            .as_vec()
-            .into_iter()
-            .zip(other.as_vec().into_iter())
-            .zip(new.as_mut_vec().into_iter())
-        {
-            *new_key = *self_key ^ other_key;
-        }
+        let new = self.union(other);
 
         // Check for invalid three- and four-key combinations
         new.validate()?;
@@ -149,35 +137,12 @@ impl Chord {
     }
 
     pub fn contains(&self, other: &Self) -> bool {
-        let zipped = self.as_vec().into_iter().zip(other.as_vec().into_iter());
-
-        let mut result = true;
-        for (self_key, other_key) in zipped {
-            // other has key set but self does not, bail out
-            if *other_key && !*self_key {
-                result = false;
-                break;
-            }
-        }
-
-        result
+        other.is_subset_of(self)
     }
 
-    pub fn full_steno_order() -> Self {
-        let mut ret: Self = Default::default();
-
-        for key in ret.as_mut_vec() {
-            *key = true;
-        }
-
-        ret
-    }
-
-    fn validate(&self) -> Result<(), ErrBox> {
-        for ch_str in INVALID_CHORDS {
-            let ch = Chord::from_str(ch_str)?;
-
-            if self.contains(&ch) {
+    pub fn validate(&self) -> Result<(), ErrBox> {
+        for ch in INVALID_CHORD_MASKS.iter() {
+            if self.contains(ch) {
                 return Err(format!(
                     "Invalid chord: contains invalid combination {}",
                     ch.to_string()
@@ -196,103 +161,23 @@ impl FromStr for Chord {
         let mut ret = Chord::default();
         let mut left_hand = true; // Are we still adding left-hand chars?
         for ch in s.to_uppercase().chars() {
-            match ch {
-                'X' => {
-                    ret.x = true;
-                }
-                'F' => {
-                    ret.f = true;
-                }
-                'Z' => {
-                    ret.z = true;
-                }
-                'S' => {
-                    if left_hand {
-                        ret.s_left = true;
-                    } else {
-                        ret.s_right = true;
-                    }
-                }
-                'K' => {
-                    ret.k = true;
-                }
-                'T' => {
-                    if left_hand {
-                        ret.t_left = true;
-                    } else {
-                        ret.t_right = true;
-                    }
-                }
-                'P' => {
-                    ret.p = true;
-                }
-                'V' => {
-                    ret.v = true;
-                }
-                'L' => {
-                    if left_hand {
-                        ret.l_left = true;
-                    } else {
-                        ret.l_right = true;
-                    }
-                }
-                'R' => {
-                    if left_hand {
-                        ret.r_left = true;
-                    } else {
-                        ret.r_right = true;
-                    }
-                }
-                'J' => {
-                    ret.j = true;
-                }
-                'E' => {
-                    ret.e = true;
-                }
-                '~' => {
-                    ret.tilde = true;
-                }
-                '*' => {
-                    ret.asterisk = true;
-                }
-                'I' => {
-                    ret.i = true;
-                }
-                'A' => {
-                    ret.a = true;
-                }
-                'U' => {
-                    ret.u = true;
-                }
-                'C' => {
-                    ret.c = true;
-                }
-                // L and R already handled above
-                'B' => {
-                    ret.b = true;
-                }
-                // S handled above
-                'G' => {
-                    ret.g = true;
-                }
-                // T handled above
-                'W' => {
-                    ret.w = true;
-                }
-                'O' => {
-                    ret.o = true;
-                }
-                'Y' => {
-                    ret.y = true;
-                }
-                // hyphen should only switch the left hand flag, which
-                // will happen automatically with the MID_CHARACTERS
-                // check
-                '-' => {}
-                other => return Err(format!("Unknown character {:?}", other).into()),
+            // hyphen should only switch the left hand flag
+            if ch == '-' {
+                left_hand = false;
+                continue;
             }
 
-            if MID_CHARACTERS.contains(ch) {
+            // Letters present on both hands (S, T, L, R) go to the
+            // left hand until we see a middle key or hyphen
+            let idx = KEYS
+                .iter()
+                .position(|k| k.letter == ch && (left_hand || k.side != Side::Left))
+                .or_else(|| KEYS.iter().position(|k| k.letter == ch))
+                .ok_or_else(|| format!("Unknown character {:?}", ch))?;
+
+            ret.0 |= Self::key_bit(idx);
+
+            if KEYS[idx].side == Side::Mid {
                 left_hand = false;
             }
         }
@@ -302,141 +187,35 @@ impl FromStr for Chord {
 
 impl ToString for Chord {
     fn to_string(&self) -> String {
-        #[deny(unused_variables)]
-        let Self {
-            x,
-            f,
-            z,
-            s_left,
-            k,
-            t_left,
-            p,
-            v,
-            l_left,
-            r_left,
-            j,
-            e,
-            tilde,
-            asterisk,
-            i,
-            a,
-            u,
-            c,
-            r_right,
-            l_right,
-            b,
-            s_right,
-            g,
-            t_right,
-            w,
-            o,
-            y,
-        } = self;
-
         let mut ret = String::new();
 
-        let mut needs_hyphen = true;
-
-        if *x {
-            ret.push('X');
-        }
-        if *f {
-            ret.push('F');
-        }
-        if *z {
-            ret.push('Z');
-        }
-        if *s_left {
-            ret.push('S');
-        }
-        if *k {
-            ret.push('K');
-        }
-        if *t_left {
-            ret.push('T');
-        }
-        if *p {
-            ret.push('P');
-        }
-        if *v {
-            ret.push('V');
-        }
-        if *l_left {
-            ret.push('L');
-        }
-        if *r_left {
-            ret.push('R');
-        }
-
-        if *j {
-            ret.push('J');
-            needs_hyphen = false;
-        }
-        if *e {
-            ret.push('E');
-            needs_hyphen = false;
-        }
-        if *tilde {
-            ret.push('~');
-            needs_hyphen = false;
-        }
-        if *asterisk {
-            ret.push('*');
-            needs_hyphen = false;
-        }
-        if *i {
-            ret.push('I');
-            needs_hyphen = false;
-        }
-        if *a {
-            ret.push('A');
-            needs_hyphen = false;
-        }
-        if *u {
-            ret.push('U');
-            needs_hyphen = false;
-        }
+        // Right-hand keys need a hyphen if no middle key disambiguates them
+        let needs_hyphen = !self.keys().any(|k| k.side == Side::Mid);
 
-        // if needs_hyphen is still set, we need to disambiguate
-        if needs_hyphen {
-            ret.push('-');
-        }
+        for (idx, key) in KEYS.iter().enumerate() {
+            if needs_hyphen
+                && idx > 0
+                && key.side == Side::Right
+                && KEYS[idx - 1].side != Side::Right
+            {
+                ret.push('-');
+            }
 
-        if *c {
-            ret.push('C');
-        }
-        if *r_right {
-            ret.push('R');
-        }
-        if *l_right {
-            ret.push('L');
-        }
-        if *b {
-            ret.push('B');
-        }
-        if *s_right {
-            ret.push('S');
-        }
-        if *g {
-            ret.push('G');
-        }
-        if *t_right {
-            ret.push('T');
-        }
-        if *w {
-            ret.push('W');
-        }
-        if *o {
-            ret.push('O');
-        }
-        if *y {
-            ret.push('Y');
+            if self.has_key(idx) {
+                ret.push(key.letter);
+            }
         }
 
         ret
     }
 }
 
+impl Debug for Chord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Chord").field(&self.to_string()).finish()
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub struct ChordSequence {
     pub items: Vec<ChordSeqItem>,
@@ -453,7 +232,7 @@ impl ChordSequence {
     }
 
     pub fn collapse(&self) -> Vec<Chord> {
-        self.items.iter().map(|i| i.collapse()).flatten().collect()
+        self.items.iter().flat_map(|i| i.collapse()).collect()
     }
 
     pub fn print_chords(&self) -> String {
@@ -463,15 +242,11 @@ impl ChordSequence {
             return String::from("<empty>");
         }
 
-        return chords
+        chords
             .iter()
             .map(|ch| ch.to_string())
             .collect::<Vec<_>>()
-            .join("/");
-    }
-
-    pub fn is_oneshot(&self) -> bool {
-	self.items.len() == 1
+            .join("/")
     }
 
     /// Re-assembles the word from sequence items
@@ -482,7 +257,9 @@ impl ChordSequence {
             let s = match item {
                 ChordSeqItem::RootChord(s, _)
                 | ChordSeqItem::Prefix(s, _)
-                | ChordSeqItem::Suffix(s, _) => s,
+                | ChordSeqItem::Suffix(s, _)
+                | ChordSeqItem::Infix(s, _) => s,
+                ChordSeqItem::Disambiguation(_) => continue,
             };
 
             ret.push_str(s);
@@ -518,14 +295,21 @@ pub enum ChordSeqItem {
     RootChord(String, Chord),
     Prefix(String, Chord),
     Suffix(String, Chord),
+    /// Joins the parts of a compound word, attaching on both sides
+    Infix(String, Chord),
+    /// Appended to a word root sharing its outline with another one,
+    /// spells nothing
+    Disambiguation(Chord),
 }
 
 impl ChordSeqItem {
     pub fn collapse(&self) -> Vec<Chord> {
         match self {
-            Self::RootChord(_s, chord) => vec![chord.clone()],
-            Self::Prefix(_s, chord) => vec![chord.clone()],
-            Self::Suffix(_s, chord) => vec![chord.clone()],
+            Self::RootChord(_s, chord) => vec![*chord],
+            Self::Prefix(_s, chord) => vec![*chord],
+            Self::Suffix(_s, chord) => vec![*chord],
+            Self::Infix(_s, chord) => vec![*chord],
+            Self::Disambiguation(chord) => vec![*chord],
         }
     }
 }
@@ -536,40 +320,44 @@ impl ToString for ChordSeqItem {
             Self::RootChord(s, ch) => format!("RC:\"{}\":{}", s, ch.to_string()),
             Self::Prefix(s, ch) => format!("P:\"{}-\":{}", s, ch.to_string()),
             Self::Suffix(s, ch) => format!("S:\"-{}\":{}", s, ch.to_string()),
+            Self::Infix(s, ch) => format!("I:\"{}\":{}", s, ch.to_string()),
+            Self::Disambiguation(ch) => format!("D:{}", ch.to_string()),
         }
     }
 }
 
 impl PartialEq for ChordSeqItem {
     fn eq(&self, other: &ChordSeqItem) -> bool {
-	self.collapse().eq(&other.collapse())
+        self.collapse().eq(&other.collapse())
     }
 }
 
 impl PartialOrd for ChordSeqItem {
     fn partial_cmp(&self, other: &ChordSeqItem) -> Option<Ordering> {
-	self.collapse().partial_cmp(&other.collapse())
+        self.collapse().partial_cmp(&other.collapse())
     }
 }
 
 impl Ord for ChordSeqItem {
     fn cmp(&self, other: &ChordSeqItem) -> Ordering {
-	self.collapse().cmp(&other.collapse())
+        self.collapse().cmp(&other.collapse())
     }
 }
 
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Every key pressed
+    fn full_steno_order() -> Chord {
+        Chord((1 << KEYS.len()) - 1)
+    }
+
     #[test]
     fn test_chord_conflict_is_detected() {
-        let mut a = Chord::default();
-        a.x = true;
+        let mut a = Chord::from_str("X").unwrap();
 
-        let mut b = Chord::default();
-        b.x = true;
+        let b = Chord::from_str("X").unwrap();
 
         assert!(a.merge(&b).is_err())
     }
@@ -592,7 +380,7 @@ mod tests {
         let full_str = "XFZSKTPVLRJE~*IAUCRLBSGTWOY";
         let parsed: Chord = full_str.parse()?;
 
-        let full_steno = Chord::full_steno_order();
+        let full_steno = full_steno_order();
 
         // Cross validate struct and generated string
         assert_eq!(parsed, full_steno);
@@ -603,7 +391,7 @@ mod tests {
 
     #[test]
     fn test_contains() {
-        let full_steno = Chord::full_steno_order();
+        let full_steno = full_steno_order();
         let empty = Chord::default();
 
         // Full steno order contains itself
@@ -621,7 +409,7 @@ mod tests {
 
     #[test]
     fn test_validate() -> Result<(), ErrBox> {
-        let full_steno = Chord::full_steno_order();
+        let full_steno = full_steno_order();
         let empty = Chord::default();
 
         // Full is never valid
@@ -638,4 +426,55 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_set_operations() -> Result<(), ErrBox> {
+        let a = Chord::from_str("XZKPLJE")?;
+        let b = Chord::from_str("KP-CLS")?;
+
+        assert_eq!(a.union(&b).to_string(), "XZKPLJECLS");
+        assert_eq!(a.intersection(&b).to_string(), "KP-");
+        assert!(a.conflicts_with(&b));
+        assert!(Chord::from_str("KP")?.is_subset_of(&a));
+        assert!(!b.is_subset_of(&a));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keys_in_steno_order() -> Result<(), ErrBox> {
+        let ch = Chord::from_str("STJ-ST")?;
+
+        let letters: String = ch.keys().map(|k| k.letter).collect();
+        assert_eq!(letters, "STJST");
+
+        let sides: Vec<_> = ch.keys().map(|k| k.side).collect();
+        assert_eq!(
+            sides,
+            vec![Side::Left, Side::Left, Side::Mid, Side::Right, Side::Right]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ord_follows_steno_order() -> Result<(), ErrBox> {
+        // Earlier keys in steno order weigh more, regardless of count
+        assert!(Chord::from_str("X")? > Chord::from_str("FZSKTPVLR-CRLBSGTWOY")?);
+        assert!(Chord::from_str("XF")? > Chord::from_str("X")?);
+        assert!(Chord::from_str("-Y")? > Chord::default());
+        assert!(Chord::from_str("S")? > Chord::from_str("-S")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hyphen_only_without_mid_keys() -> Result<(), ErrBox> {
+        assert_eq!(Chord::from_str("X")?.to_string(), "X-");
+        assert_eq!(Chord::from_str("-T")?.to_string(), "-T");
+        assert_eq!(Chord::from_str("T*T")?.to_string(), "T*T");
+        assert_eq!(Chord::default().to_string(), "-");
+
+        Ok(())
+    }
 }
@@ -0,0 +1,1194 @@
+//! Command-line interface
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use indicatif::{ProgressBar, ProgressStyle};
+use log::{debug, error, info, warn};
+
+use std::{
+    cmp::Reverse,
+    collections::BTreeSet,
+    fs::{self, File},
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    conflicts::{ConflictResolver, ResolutionAction},
+    frequency,
+    generator::{sanitize_word, ChunkStrategy, Generator, SplitStrategy},
+    lexeme::{self, LexemeReport, Lexemes},
+    lint::{self, LintKind, LintReport},
+    morphology::TableMorphology,
+    output::{DictFormat, PloverJson, PythonModule, Tsv, SYLLABLES_FILE, WORD_ROOTS_FILE},
+    plover_engine::{parse_outline, StenoDictionary},
+    plover_system::{PloverSystem, DEFAULT_DICTIONARIES_ROOT, DEFAULT_SYSTEM_NAME},
+    split_compare,
+    theory::Theory,
+    translate::Translator,
+    utils::LenSortableString,
+    verify::{self, VerifyStatus},
+    word_source::{WordListFormat, WordSource},
+    ErrBox,
+};
+
+#[derive(Parser)]
+#[command(about = "Generates Plover dictionaries for Polish steno")]
+pub struct Cli {
+    /// JSON theory file replacing sections of the built-in theory
+    #[arg(long, global = true, value_name = "PATH")]
+    pub theory: Option<PathBuf>,
+
+    /// More logging, repeat for debug and trace output
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Errors only
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Process a word list and write the Plover dictionaries
+    Generate {
+        #[command(flatten)]
+        input: WordInput,
+
+        #[command(flatten)]
+        gen_opts: GeneratorOpts,
+
+        /// Format of the syllables and word roots dictionaries
+        #[arg(long, value_enum, default_value_t = DictFormatArg::PloverJson)]
+        format: DictFormatArg,
+
+        #[arg(long, value_name = "PATH", default_value = SYLLABLES_FILE)]
+        syllables_out: PathBuf,
+
+        #[arg(long, value_name = "PATH", default_value = WORD_ROOTS_FILE)]
+        word_roots_out: PathBuf,
+
+        #[arg(long, value_name = "PATH", default_value = "conflict_report.json")]
+        conflict_report_out: PathBuf,
+
+        /// Written only when a frequency list is given
+        #[arg(
+            long,
+            value_name = "PATH",
+            default_value = "word_root_frequencies.json"
+        )]
+        frequencies_out: PathBuf,
+
+        /// Keep conflicting word roots as they are
+        #[arg(long)]
+        no_resolve: bool,
+
+        /// Written only with --lexemes
+        #[arg(long, value_name = "PATH", default_value = "lexeme_report.json")]
+        lexeme_report_out: PathBuf,
+
+        /// Also write word roots and syllables as an RTF/CRE dictionary
+        #[arg(long, value_name = "PATH")]
+        rtf_out: Option<PathBuf>,
+
+        /// Entries left out of the RTF/CRE dictionary, written only with
+        /// --rtf-out
+        #[arg(long, value_name = "PATH", default_value = "rtf_report.json")]
+        rtf_report_out: PathBuf,
+
+        /// Also write where every word root and syllables entry comes from
+        #[arg(long, value_name = "PATH")]
+        provenance_out: Option<PathBuf>,
+    },
+    /// Print the outline and alternative candidates for a word
+    Lookup {
+        word: String,
+
+        /// How many candidate outlines to show
+        #[arg(short = 'n', long, default_value_t = 5)]
+        candidates: usize,
+
+        #[command(flatten)]
+        input: WordInput,
+
+        #[command(flatten)]
+        gen_opts: GeneratorOpts,
+    },
+    /// Process a word list and list conflicting outlines
+    Conflicts {
+        #[command(flatten)]
+        input: WordInput,
+
+        #[command(flatten)]
+        gen_opts: GeneratorOpts,
+
+        /// Also run conflict resolution and write its report here
+        #[arg(long, value_name = "PATH")]
+        resolve: Option<PathBuf>,
+    },
+    /// Show how a word is split into affixes and syllables
+    Split {
+        word: String,
+
+        #[command(flatten)]
+        gen_opts: GeneratorOpts,
+    },
+    /// Compare outline lengths of the stroke-aware split strategy
+    /// against --split-strategy
+    CompareSplit {
+        #[command(flatten)]
+        input: WordInput,
+
+        #[command(flatten)]
+        gen_opts: GeneratorOpts,
+
+        #[arg(long, value_name = "PATH", default_value = "split_comparison.json")]
+        report: PathBuf,
+
+        /// Words with the largest differences to print
+        #[arg(long, default_value_t = 10)]
+        examples: usize,
+    },
+    /// Print the strokes for a passage of text, flagging untranslatable tokens
+    Translate {
+        /// Text to translate, read from --input or stdin if missing
+        text: Option<String>,
+
+        #[arg(long, value_name = "PATH", conflicts_with = "text")]
+        input: Option<PathBuf>,
+
+        /// Word list to generate (and resolve conflicts) first, so that
+        /// outlines match the generated dictionaries
+        #[command(flatten)]
+        words: WordInput,
+
+        #[command(flatten)]
+        gen_opts: GeneratorOpts,
+    },
+    /// Translate an outline back to text, the way Plover would
+    Untranslate {
+        /// Strokes separated with "/", e.g. PRE*T/ZSKAU/LA/XFKI
+        outline: String,
+
+        /// Plover JSON dictionary to use instead of generating one,
+        /// highest priority first
+        #[arg(long = "dictionary", value_name = "PATH")]
+        dictionaries: Vec<PathBuf>,
+
+        #[command(flatten)]
+        words: WordInput,
+
+        #[command(flatten)]
+        gen_opts: GeneratorOpts,
+    },
+    /// Translate every word's outline back to text and report the
+    /// words that don't round-trip
+    Verify {
+        #[command(flatten)]
+        input: WordInput,
+
+        #[command(flatten)]
+        gen_opts: GeneratorOpts,
+
+        #[arg(long, value_name = "PATH", default_value = "verify_report.json")]
+        report: PathBuf,
+
+        /// Report format, guessed from the report extension by default
+        #[arg(long, value_enum)]
+        format: Option<ReportFormat>,
+
+        /// Keep conflicting word roots as they are
+        #[arg(long)]
+        no_resolve: bool,
+    },
+    /// Check the theory tables for broken, clashing and unused entries.
+    /// Unused entries are only looked for with a word list.
+    Lint {
+        #[command(flatten)]
+        input: WordInput,
+
+        #[command(flatten)]
+        gen_opts: GeneratorOpts,
+
+        #[arg(long, value_name = "PATH", default_value = "lint_report.json")]
+        report: PathBuf,
+    },
+    /// Read words from stdin and print their outlines
+    Repl {
+        #[command(flatten)]
+        input: WordInput,
+
+        #[command(flatten)]
+        gen_opts: GeneratorOpts,
+    },
+    /// Write a Plover system plugin module describing the key layout
+    PloverSystem {
+        #[arg(value_name = "PATH")]
+        out: PathBuf,
+
+        #[arg(long, default_value = DEFAULT_SYSTEM_NAME)]
+        name: String,
+
+        /// Where Plover looks for the dictionaries
+        #[arg(long, value_name = "PATH", default_value = DEFAULT_DICTIONARIES_ROOT)]
+        dictionaries_root: String,
+
+        /// Dictionary to load by default, highest priority first
+        #[arg(
+            long = "dictionary",
+            value_name = "PATH",
+            default_values = [WORD_ROOTS_FILE, SYLLABLES_FILE]
+        )]
+        dictionaries: Vec<String>,
+    },
+    /// Write the effective theory as JSON, a starting point for theory files
+    DumpTheory {
+        #[arg(value_name = "PATH")]
+        out: PathBuf,
+    },
+}
+
+#[derive(Args, Default)]
+pub struct WordInput {
+    /// Word list in odm format, - for stdin
+    #[arg(long, value_name = "PATH", conflicts_with = "list")]
+    pub odm: Option<PathBuf>,
+
+    /// Word list with one word per line, - for stdin
+    #[arg(long, value_name = "PATH")]
+    pub list: Option<PathBuf>,
+
+    /// Frequency list in word<TAB>count format
+    #[arg(long, value_name = "PATH")]
+    pub frequencies: Option<PathBuf>,
+
+    /// Words at least this frequent get their shortest free outline
+    #[arg(long, value_name = "COUNT", requires = "frequencies")]
+    pub frequent_threshold: Option<u64>,
+}
+
+impl WordInput {
+    /// None if no word list was given and none is embedded
+    pub fn source(&self) -> Option<WordSource> {
+        let from_path = |path: &Path, format| {
+            if path == Path::new("-") {
+                WordSource::Stdin(format)
+            } else {
+                WordSource::File(path.to_owned(), format)
+            }
+        };
+
+        match (&self.odm, &self.list) {
+            (Some(path), _) => Some(from_path(path, WordListFormat::Odm)),
+            (None, Some(path)) => Some(from_path(path, WordListFormat::Plain)),
+            #[cfg(feature = "embedded-odm")]
+            (None, None) => Some(WordSource::Embedded),
+            #[cfg(not(feature = "embedded-odm"))]
+            (None, None) => None,
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct GeneratorOpts {
+    #[arg(long, value_enum, default_value_t = ChunkStrategyArg::Backtracking)]
+    pub chunk_strategy: ChunkStrategyArg,
+
+    /// How word roots are split into syllables
+    #[arg(long, value_enum, default_value_t = SplitStrategyArg::Rough)]
+    pub split_strategy: SplitStrategyArg,
+
+    /// How many prefixes may be stripped off a word, e.g. 2 for
+    /// nie-do-
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    pub max_prefixes: usize,
+
+    /// How many suffixes may be stripped off a word, e.g. 2 for
+    /// -ość-ami
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    pub max_suffixes: usize,
+
+    /// Split compounds at an interfix when both parts are known word
+    /// roots, e.g. dług-o-pis. Hyphenated words are always split.
+    #[arg(long)]
+    pub compounds: bool,
+
+    /// Add the forms of each lexeme together, so that they share one
+    /// root outline
+    #[arg(long, value_enum)]
+    pub lexemes: Option<LexemeSourceArg>,
+
+    /// Morphological analyses in form<TAB>lemma<TAB>tag format, as in
+    /// PoliMorf
+    #[arg(long, value_name = "PATH")]
+    pub morphology_table: Option<PathBuf>,
+
+    /// Analyse words with Morfeusz2
+    #[cfg(feature = "morfeusz")]
+    #[arg(long, conflicts_with = "morphology_table")]
+    pub morfeusz: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LexemeSourceArg {
+    /// Each line of an odm word list is one lexeme
+    Odm,
+    /// Group words by lemma, needs --morphology-table or --morfeusz
+    Lemma,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ReportFormat {
+    Json,
+    Csv,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum DictFormatArg {
+    PloverJson,
+    /// Plover JSON without whitespace
+    PloverJsonCompact,
+    /// Plover Python dictionary, needs plover-python-dictionary
+    Python,
+    /// outline<TAB>translation<TAB>source lines
+    Tsv,
+}
+
+impl DictFormatArg {
+    pub fn dict_format(self) -> Box<dyn DictFormat> {
+        match self {
+            Self::PloverJson => Box::new(PloverJson { pretty: true }),
+            Self::PloverJsonCompact => Box::new(PloverJson { pretty: false }),
+            Self::Python => Box::new(PythonModule),
+            Self::Tsv => Box::new(Tsv),
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ChunkStrategyArg {
+    Greedy,
+    Backtracking,
+}
+
+impl From<ChunkStrategyArg> for ChunkStrategy {
+    fn from(arg: ChunkStrategyArg) -> Self {
+        match arg {
+            ChunkStrategyArg::Greedy => Self::Greedy,
+            ChunkStrategyArg::Backtracking => Self::Backtracking,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SplitStrategyArg {
+    Rough,
+    MaximalOnset,
+    /// Chunk word roots by what fits in a stroke, not by syllables
+    StrokeAware,
+}
+
+impl From<SplitStrategyArg> for SplitStrategy {
+    fn from(arg: SplitStrategyArg) -> Self {
+        match arg {
+            SplitStrategyArg::Rough => Self::Rough,
+            SplitStrategyArg::MaximalOnset => Self::MaximalOnset,
+            SplitStrategyArg::StrokeAware => Self::StrokeAware,
+        }
+    }
+}
+
+impl Cli {
+    pub fn log_level(&self) -> log::LevelFilter {
+        if self.quiet {
+            return log::LevelFilter::Error;
+        }
+
+        match self.verbose {
+            0 => log::LevelFilter::Warn,
+            1 => log::LevelFilter::Info,
+            2 => log::LevelFilter::Debug,
+            _ => log::LevelFilter::Trace,
+        }
+    }
+
+    pub fn run(self) -> Result<(), ErrBox> {
+        let theory = match &self.theory {
+            Some(path) => {
+                // Lint reports broken chords itself
+                let theory = if matches!(self.command, Command::Lint { .. }) {
+                    Theory::from_file_unchecked(path)?
+                } else {
+                    Theory::from_file(path)?
+                };
+                info!("Loaded theory from {}", path.display());
+                theory
+            }
+            None => Theory::builtin(),
+        };
+
+        match self.command {
+            Command::Generate {
+                input,
+                gen_opts,
+                format,
+                syllables_out,
+                word_roots_out,
+                conflict_report_out,
+                frequencies_out,
+                no_resolve,
+                lexeme_report_out,
+                rtf_out,
+                rtf_report_out,
+                provenance_out,
+            } => {
+                let source = input
+                    .source()
+                    .ok_or("No word list given, use --odm or --list")?;
+                let mut gen = new_generator(theory, &input, &gen_opts)?;
+                let (_words, lexeme_report) = process_words(&mut gen, &source, &gen_opts)?;
+
+                if let Some(report) = lexeme_report {
+                    println!(
+                        "{} lexemes, {} forms share a root, {} stem/suffix disagreements, {} failures",
+                        report.lexemes,
+                        report.consistent_forms,
+                        report.disagreements.len(),
+                        report.failures.len()
+                    );
+                    report.save(File::create(&lexeme_report_out)?)?;
+                    println!("Wrote lexeme report to {}", lexeme_report_out.display());
+                }
+
+                print_conflict_summary(&gen);
+
+                if !no_resolve {
+                    let report = ConflictResolver::default().resolve(&mut gen);
+                    print_resolution_summary(&report);
+                    report.save(File::create(&conflict_report_out)?)?;
+                    println!("Wrote conflict report to {}", conflict_report_out.display());
+                }
+
+                let format = format.dict_format();
+
+                gen.save_syllables(format.as_ref(), File::create(&syllables_out)?)?;
+                println!("Wrote syllables to {}", syllables_out.display());
+
+                gen.save_word_roots(format.as_ref(), File::create(&word_roots_out)?)?;
+                println!("Wrote word roots to {}", word_roots_out.display());
+
+                if !gen.word_frequencies.is_empty() {
+                    gen.save_word_root_frequencies(File::create(&frequencies_out)?)?;
+                    println!(
+                        "Wrote word root frequencies to {}",
+                        frequencies_out.display()
+                    );
+                }
+
+                if let Some(rtf_out) = rtf_out {
+                    let report = gen.save_rtf(File::create(&rtf_out)?)?;
+                    println!(
+                        "Wrote {} RTF/CRE entries to {}",
+                        report.written,
+                        rtf_out.display()
+                    );
+
+                    if !report.skipped.is_empty() {
+                        for skipped in report.skipped.iter() {
+                            warn!(
+                                "RTF SKIP:\t{} {:?}: {}",
+                                skipped.outline, skipped.translation, skipped.reason
+                            );
+                        }
+                        println!(
+                            "{} entries have no RTF/CRE equivalent, see {}",
+                            report.skipped.len(),
+                            rtf_report_out.display()
+                        );
+                    }
+                    report.save(File::create(&rtf_report_out)?)?;
+                }
+
+                if let Some(provenance_out) = provenance_out {
+                    gen.provenance_report()
+                        .save(File::create(&provenance_out)?)?;
+                    println!("Wrote provenance to {}", provenance_out.display());
+                }
+            }
+            Command::Lookup {
+                word,
+                candidates,
+                input,
+                gen_opts,
+            } => {
+                let mut gen = new_generator(theory, &input, &gen_opts)?;
+                if let Some(source) = input.source() {
+                    process_words(&mut gen, &source, &gen_opts)?;
+                }
+
+                print_word(&gen, &word, candidates)?;
+            }
+            Command::Conflicts {
+                input,
+                gen_opts,
+                resolve,
+            } => {
+                let source = input
+                    .source()
+                    .ok_or("No word list given, use --odm or --list")?;
+                let mut gen = new_generator(theory, &input, &gen_opts)?;
+                process_words(&mut gen, &source, &gen_opts)?;
+
+                let mut conflicts: Vec<_> = gen
+                    .word_root_conflict_dict
+                    .iter()
+                    .filter(|(_outline, words)| words.len() > 1)
+                    .collect();
+                conflicts.sort_by_key(|(_outline, words)| words.len());
+
+                for (outline, words) in conflicts.iter() {
+                    println!("{}\t{:?}", outline.print_chords(), words);
+                }
+
+                print_conflict_summary(&gen);
+
+                if let Some(report_path) = resolve {
+                    let report = ConflictResolver::default().resolve(&mut gen);
+                    print_resolution_summary(&report);
+                    report.save(File::create(&report_path)?)?;
+                    println!("Wrote conflict report to {}", report_path.display());
+                }
+            }
+            Command::Split { word, gen_opts } => {
+                let gen = new_generator(theory, &WordInput::default(), &gen_opts)?;
+                let word = sanitize_word(&word)?;
+
+                let (chords, _new_chunks) = gen.gen_word_chords(&word)?;
+                println!("Affixes: {}", chords.to_string());
+                println!("Syllables: {}", gen.split_syllables(&word).join("-"));
+            }
+            Command::CompareSplit {
+                input,
+                gen_opts,
+                report: report_path,
+                examples,
+            } => {
+                let source = input
+                    .source()
+                    .ok_or("No word list given, use --odm or --list")?;
+                let mut gen = new_generator(theory, &input, &gen_opts)?;
+
+                let baseline = gen.split_strategy;
+                if baseline == SplitStrategy::StrokeAware {
+                    return Err("Pick a syllable-based --split-strategy to compare against".into());
+                }
+
+                let words = read_words(&gen, &source)?;
+                let report = split_compare::compare_words(&mut gen, baseline, words.iter());
+
+                println!(
+                    "{} words: {} strokes with {:?}, {} stroke-aware ({} saved)",
+                    report.compared,
+                    report.baseline_strokes,
+                    baseline,
+                    report.stroke_aware_strokes,
+                    report.strokes_saved()
+                );
+                println!(
+                    "  {} shorter, {} longer, {} rejected",
+                    report.improved,
+                    report.worsened,
+                    report.rejected.len()
+                );
+                for d in report.differences.iter().take(examples) {
+                    println!(
+                        "  {}\t{} -> {}",
+                        d.word, d.baseline_outline, d.stroke_aware_outline
+                    );
+                }
+
+                report.save(File::create(&report_path)?)?;
+                println!("Wrote split comparison to {}", report_path.display());
+            }
+            Command::Translate {
+                text,
+                input,
+                words,
+                gen_opts,
+            } => {
+                let mut gen = new_generator(theory, &words, &gen_opts)?;
+                if let Some(source) = words.source() {
+                    process_words(&mut gen, &source, &gen_opts)?;
+                    ConflictResolver::default().resolve(&mut gen);
+                }
+
+                let text = match (text, input) {
+                    (Some(text), _) => text,
+                    (None, Some(path)) => fs::read_to_string(&path)
+                        .map_err(|e| format!("{}: {}", path.display(), e))?,
+                    (None, None) => io::read_to_string(io::stdin())?,
+                };
+
+                let translated = Translator::new(&gen)?.translate(&text);
+
+                for t in translated.iter() {
+                    match &t.error {
+                        Some(e) => println!("{}\t!\t{}", t.token.text(), e),
+                        None => println!("{}\t{}", t.token.text(), t.strokes.join("/")),
+                    }
+                }
+
+                let strokes: Vec<&str> = translated
+                    .iter()
+                    .flat_map(|t| t.strokes.iter().map(|s| s.as_str()))
+                    .collect();
+
+                println!(
+                    "{}/{} tokens translated",
+                    translated.iter().filter(|t| t.error.is_none()).count(),
+                    translated.len()
+                );
+                println!("Strokes: {}", strokes.join("/"));
+            }
+            Command::Untranslate {
+                outline,
+                dictionaries,
+                words,
+                gen_opts,
+            } => {
+                let dict = if dictionaries.is_empty() {
+                    let mut gen = new_generator(theory, &words, &gen_opts)?;
+                    if let Some(source) = words.source() {
+                        process_words(&mut gen, &source, &gen_opts)?;
+                        ConflictResolver::default().resolve(&mut gen);
+                    }
+                    StenoDictionary::from_generator(&gen)?
+                } else {
+                    let readers = dictionaries
+                        .iter()
+                        .map(|path| {
+                            File::open(path)
+                                .map(BufReader::new)
+                                .map_err(|e| format!("{}: {}", path.display(), e))
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    StenoDictionary::from_json_readers(readers)?
+                };
+
+                println!("{}", dict.strokes_to_text(&parse_outline(&outline)?));
+            }
+            Command::Verify {
+                input,
+                gen_opts,
+                report: report_path,
+                format,
+                no_resolve,
+            } => {
+                let source = input
+                    .source()
+                    .ok_or("No word list given, use --odm or --list")?;
+                let mut gen = new_generator(theory, &input, &gen_opts)?;
+                let (words, _lexeme_report) = process_words(&mut gen, &source, &gen_opts)?;
+
+                if !no_resolve {
+                    ConflictResolver::default().resolve(&mut gen);
+                }
+
+                let dict = StenoDictionary::from_generator(&gen)?;
+                let report = verify::verify_words(&gen, &dict, words.iter());
+
+                println!(
+                    "{}/{} words round-trip",
+                    report.count(VerifyStatus::Ok),
+                    report.checked
+                );
+                for (status, count) in report.counts.iter() {
+                    if *status != VerifyStatus::Ok {
+                        println!("  {}: {}", status.as_str(), count);
+                    }
+                }
+
+                let format =
+                    format.unwrap_or(match report_path.extension().and_then(|ext| ext.to_str()) {
+                        Some("csv") => ReportFormat::Csv,
+                        _other => ReportFormat::Json,
+                    });
+
+                let f = File::create(&report_path)?;
+                match format {
+                    ReportFormat::Json => report.save_json(f)?,
+                    ReportFormat::Csv => report.save_csv(f)?,
+                }
+                println!("Wrote verification report to {}", report_path.display());
+            }
+            Command::Lint {
+                input,
+                gen_opts,
+                report: report_path,
+            } => {
+                let mut report = LintReport::default();
+                report.extend(lint::lint_theory(&theory));
+
+                // Invalid combinations still parse, so the generator
+                // works with them, the built-in theory has a few
+                if report.count(LintKind::Unparseable) > 0 {
+                    println!("Unparseable chords, skipping the unused entries check");
+                } else if let Some(source) = input.source() {
+                    let mut gen = new_generator(theory, &input, &gen_opts)?;
+                    let (words, _lexeme_report) = process_words(&mut gen, &source, &gen_opts)?;
+
+                    report.extend(lint::shadowed_entries(&gen, words.iter()));
+                    report.words_checked = Some(words.len());
+                } else {
+                    println!("No word list given, skipping the unused entries check");
+                }
+
+                for issue in report.issues.iter() {
+                    println!(
+                        "{}:\t{} {:?} => {:?}: {}",
+                        issue.kind.as_str().to_uppercase(),
+                        issue.section,
+                        issue.key,
+                        issue.chord,
+                        issue.message
+                    );
+                }
+
+                println!("{} lint issues", report.issues.len());
+                for (kind, count) in report.counts.iter() {
+                    println!("  {}: {}", kind.as_str(), count);
+                }
+
+                report.save(File::create(&report_path)?)?;
+                println!("Wrote lint report to {}", report_path.display());
+            }
+            Command::Repl { input, gen_opts } => {
+                let mut gen = new_generator(theory, &input, &gen_opts)?;
+                if let Some(source) = input.source() {
+                    process_words(&mut gen, &source, &gen_opts)?;
+                }
+
+                for line in io::stdin().lock().lines() {
+                    let line = line?;
+
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+
+                    if let Err(e) = print_word(&gen, &line, 5) {
+                        error!("{}", e.to_string());
+                    }
+                }
+            }
+            Command::PloverSystem {
+                out,
+                name,
+                dictionaries_root,
+                dictionaries,
+            } => {
+                let system = PloverSystem {
+                    name,
+                    dictionaries_root,
+                    dictionaries,
+                };
+                system.write_module(File::create(&out)?)?;
+                println!("Wrote Plover system to {}", out.display());
+            }
+            Command::DumpTheory { out } => {
+                theory.save(File::create(&out)?)?;
+                println!("Wrote theory to {}", out.display());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn new_generator(
+    theory: Theory,
+    input: &WordInput,
+    gen_opts: &GeneratorOpts,
+) -> Result<Generator, ErrBox> {
+    let mut gen = Generator::new(theory)?;
+
+    gen.chunk_strategy = gen_opts.chunk_strategy.into();
+    gen.split_strategy = gen_opts.split_strategy.into();
+    gen.max_prefixes = gen_opts.max_prefixes;
+    gen.max_suffixes = gen_opts.max_suffixes;
+    gen.split_compounds = gen_opts.compounds;
+
+    if let Some(path) = &input.frequencies {
+        gen.word_frequencies = frequency::load_frequencies(BufReader::new(File::open(path)?))
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        info!(
+            "Loaded {} word frequencies from {}",
+            gen.word_frequencies.len(),
+            path.display()
+        );
+    }
+    gen.frequent_word_threshold = input.frequent_threshold;
+
+    if let Some(path) = &gen_opts.morphology_table {
+        let morph = TableMorphology::from_tsv(File::open(path)?)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        info!(
+            "Loaded analyses of {} words from {}",
+            morph.entries.len(),
+            path.display()
+        );
+        gen.morphology = Some(Box::new(morph));
+    }
+
+    #[cfg(feature = "morfeusz")]
+    if gen_opts.morfeusz {
+        gen.morphology = Some(Box::new(crate::morphology::MorfeuszMorphology::new()?));
+    }
+
+    Ok(gen)
+}
+
+/// Distinct words of source in processing order
+fn read_words(gen: &Generator, source: &WordSource) -> Result<Vec<String>, ErrBox> {
+    let sjp_sanitized_len_sorted: BTreeSet<LenSortableString<true>> = source
+        .read_groups()?
+        .into_iter()
+        .flatten()
+        .map(|word| word.into())
+        .collect();
+
+    // Frequent words go first, so that they get the first pick of outlines
+    let mut sjp_words: Vec<_> = sjp_sanitized_len_sorted.into_iter().collect();
+    sjp_words.sort_by_key(|w| Reverse(gen.frequency(&w.0)));
+
+    Ok(sjp_words.into_iter().map(|w| w.0).collect())
+}
+
+fn progress_bar(len: usize) -> Result<ProgressBar, ErrBox> {
+    Ok(
+        ProgressBar::new(len as u64).with_style(ProgressStyle::with_template(
+            "[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg} [{per_sec}]",
+        )?),
+    )
+}
+
+/// Feed every word of source into gen, returning the words in
+/// processing order. The lexeme report is there only with --lexemes.
+fn process_words(
+    gen: &mut Generator,
+    source: &WordSource,
+    gen_opts: &GeneratorOpts,
+) -> Result<(Vec<String>, Option<LexemeReport>), ErrBox> {
+    let (lexemes, unknown) = match gen_opts.lexemes {
+        None => {
+            let words = read_words(gen, source)?;
+            add_words(gen, &words)?;
+            return Ok((words, None));
+        }
+        Some(LexemeSourceArg::Odm) => {
+            if matches!(
+                source,
+                WordSource::File(_, WordListFormat::Plain)
+                    | WordSource::Stdin(WordListFormat::Plain)
+            ) {
+                warn!("--lexemes odm needs an odm word list, every word is its own lexeme");
+            }
+            (lexeme::odm_lexemes(source.read_groups()?), vec![])
+        }
+        Some(LexemeSourceArg::Lemma) => {
+            let words = read_words(gen, source)?;
+
+            let morph = gen.morphology.as_deref().ok_or(
+                "--lexemes lemma needs --morphology-table, or --morfeusz with a build from morfeusz/Cargo.toml",
+            )?;
+
+            lexeme::group_by_lemma(morph, &words)?
+        }
+    };
+
+    info!(
+        "{} lexemes, {} words outside any lexeme",
+        lexemes.len(),
+        unknown.len()
+    );
+
+    let (mut words, report) = add_lexemes(gen, lexemes)?;
+
+    add_words(gen, &unknown)?;
+    words.extend(unknown);
+
+    Ok((words, Some(report)))
+}
+
+fn add_words(gen: &mut Generator, words: &[String]) -> Result<(), ErrBox> {
+    let bar = progress_bar(words.len())?;
+
+    for (idx, word) in words.iter().enumerate() {
+        // One word the generator can't handle shouldn't cost the rest
+        if let Err(e) = gen.add_word_root(word) {
+            warn!("WORD SKIP:\t{} ({})", word, e);
+        }
+
+        // Don't hog I/O for the progress bar
+        if idx % 1000 == 0 {
+            bar.set_message(word.clone());
+            bar.inc(1000);
+        }
+    }
+
+    bar.finish();
+
+    info!("{} distinct word roots created", gen.word_root_dict.len());
+    info!("{} distinct word chunks created", gen.chunk_dict.len());
+
+    Ok(())
+}
+
+/// Add each lexeme with a shared root outline, the most frequent ones
+/// first. A form listed under several lexemes is added with the first
+/// one only.
+fn add_lexemes(
+    gen: &mut Generator,
+    lexemes: Lexemes,
+) -> Result<(Vec<String>, LexemeReport), ErrBox> {
+    let mut lexemes: Vec<_> = lexemes.into_iter().collect();
+    lexemes
+        .sort_by_key(|(_lemma, forms)| Reverse(forms.iter().map(|form| gen.frequency(form)).max()));
+
+    let mut report = LexemeReport::default();
+    let mut words = Vec::new();
+    let mut seen = BTreeSet::new();
+
+    let bar = progress_bar(lexemes.len())?;
+
+    for (idx, (lemma, forms)) in lexemes.into_iter().enumerate() {
+        let forms: BTreeSet<String> = forms.difference(&seen).cloned().collect();
+
+        if !forms.is_empty() {
+            lexeme::add_lexeme(gen, &lemma, &forms, &mut report)?;
+
+            words.extend(forms.iter().cloned());
+            seen.extend(forms);
+        }
+
+        if idx % 1000 == 0 {
+            bar.set_message(lemma);
+            bar.inc(1000);
+        }
+    }
+
+    bar.finish();
+
+    Ok((words, report))
+}
+
+fn print_conflict_summary(gen: &Generator) {
+    for (stroke, chunks) in gen.chunk_conflict_dict.iter() {
+        if chunks.len() > 1 {
+            debug!("CHUNK CONFLICT {} -> {:?}", stroke.print_chords(), chunks);
+        }
+    }
+
+    println!(
+        "{}/{} chunk outlines have conflicts",
+        gen.chunk_conflict_dict
+            .values()
+            .filter(|chunks| chunks.len() > 1)
+            .count(),
+        gen.chunk_conflict_dict.len()
+    );
+
+    println!(
+        "{}/{} word root outlines have conflicts",
+        gen.word_root_conflict_dict
+            .values()
+            .filter(|word_roots| word_roots.len() > 1)
+            .count(),
+        gen.word_root_conflict_dict.len()
+    );
+}
+
+fn print_resolution_summary(report: &crate::conflicts::ConflictReport) {
+    println!(
+        "{} conflicting word roots reassigned, {} unresolved",
+        report.decisions.len()
+            - report.count(ResolutionAction::Kept)
+            - report.count(ResolutionAction::Unresolved),
+        report.count(ResolutionAction::Unresolved)
+    );
+}
+
+/// Print the outline of word, plus alternatives if its root outline
+/// collides with another word. Leaves gen as it is.
+fn print_word(gen: &Generator, word: &str, n_candidates: usize) -> Result<(), ErrBox> {
+    let chords = gen.word_outline(word)?;
+
+    println!("Chords: {}", chords.print_chords());
+    println!("Full expansion: {}", chords.to_string());
+
+    if gen.is_root_outline_taken(&chords) {
+        println!("Root outline collides with another word, alternatives:");
+        for candidate in gen.gen_word_candidates(word, n_candidates)? {
+            println!(
+                "  {}{} ({} strokes, {} keys)",
+                candidate.chords.print_chords(),
+                if gen.is_root_outline_taken(&candidate.chords) {
+                    " [taken]"
+                } else {
+                    ""
+                },
+                candidate.score.strokes,
+                candidate.score.keys,
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> Result<PathBuf, ErrBox> {
+        let dir =
+            std::env::temp_dir().join(format!("plover-pl-cli-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    #[test]
+    fn test_parse_args() -> Result<(), ErrBox> {
+        let cli = Cli::try_parse_from([
+            "prog",
+            "-vv",
+            "lookup",
+            "kot",
+            "-n",
+            "3",
+            "--list",
+            "words.txt",
+        ])?;
+        assert_eq!(cli.log_level(), log::LevelFilter::Debug);
+        match cli.command {
+            Command::Lookup {
+                word,
+                candidates,
+                input,
+                ..
+            } => {
+                assert_eq!(word, "kot");
+                assert_eq!(candidates, 3);
+                assert_eq!(input.list, Some(PathBuf::from("words.txt")));
+            }
+            _other => panic!("expected lookup"),
+        }
+
+        let cli = Cli::try_parse_from(["prog", "generate", "--no-resolve"])?;
+        match cli.command {
+            Command::Generate {
+                word_roots_out,
+                no_resolve,
+                rtf_out,
+                ..
+            } => {
+                assert_eq!(word_roots_out, PathBuf::from(WORD_ROOTS_FILE));
+                assert!(no_resolve);
+                assert_eq!(rtf_out, None);
+            }
+            _other => panic!("expected generate"),
+        }
+
+        let cli = Cli::try_parse_from(["prog", "plover-system", "system.py"])?;
+        match cli.command {
+            Command::PloverSystem { dictionaries, .. } => {
+                assert_eq!(dictionaries, vec![WORD_ROOTS_FILE, SYLLABLES_FILE]);
+            }
+            _other => panic!("expected plover-system"),
+        }
+
+        // Conflicting and missing arguments
+        assert!(Cli::try_parse_from(["prog", "-q", "-v", "lookup", "kot"]).is_err());
+        assert!(
+            Cli::try_parse_from(["prog", "lookup", "kot", "--odm", "a", "--list", "b"]).is_err()
+        );
+        assert!(Cli::try_parse_from(["prog", "lookup"]).is_err());
+        assert!(Cli::try_parse_from(["prog", "translate", "kot", "--input", "a"]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_words_skips_rejected() -> Result<(), ErrBox> {
+        let mut gen = Generator::new(Theory::builtin())?;
+        let n_roots = gen.word_root_dict.len();
+
+        let words: Vec<String> = ["kot", "ab-", "pies"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        add_words(&mut gen, &words)?;
+
+        assert!(gen.word_root_dict.contains_key(&"kot".to_owned().into()));
+        assert!(gen.word_root_dict.contains_key(&"pies".to_owned().into()));
+        assert_eq!(gen.word_root_dict.len(), n_roots + 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_print_word_leaves_generator() -> Result<(), ErrBox> {
+        let mut gen = Generator::new(Theory::builtin())?;
+        add_words(&mut gen, &["kot".to_owned()])?;
+        let n_roots = gen.word_root_dict.len();
+        let n_chunks = gen.chunk_dict.len();
+
+        print_word(&gen, "pies", 5)?;
+
+        assert!(!gen.word_root_dict.contains_key(&"pies".to_owned().into()));
+        assert_eq!(gen.word_root_dict.len(), n_roots);
+        assert_eq!(gen.chunk_dict.len(), n_chunks);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_subcommands_smoke() -> Result<(), ErrBox> {
+        let dir = scratch_dir("smoke")?;
+        let list = dir.join("words.txt");
+        fs::write(&list, "kot\nab-\npies\nkotek\npiesek\n")?;
+        let list = list.to_str().ok_or("non-UTF-8 temp dir")?;
+
+        let run = |args: &[&str]| -> Result<(), ErrBox> {
+            Cli::try_parse_from(["prog", "-q"].iter().chain(args))?.run()
+        };
+
+        run(&["lookup", "kot", "--list", list])?;
+        run(&["conflicts", "--list", list])?;
+        run(&["split", "piesek"])?;
+
+        let system_out = dir.join("system.py");
+        run(&[
+            "plover-system",
+            system_out.to_str().ok_or("non-UTF-8 temp dir")?,
+        ])?;
+        assert!(fs::read_to_string(&system_out)?.contains(WORD_ROOTS_FILE));
+
+        let theory_out = dir.join("theory.json");
+        run(&[
+            "dump-theory",
+            theory_out.to_str().ok_or("non-UTF-8 temp dir")?,
+        ])?;
+        run(&[
+            "--theory",
+            theory_out.to_str().ok_or("non-UTF-8 temp dir")?,
+            "lookup",
+            "pies",
+        ])?;
+
+        fs::remove_dir_all(&dir)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,312 @@
+//! Conflict resolution for word-root outlines. Every word sharing an
+//! outline with another word gets a unique outline assigned, and each
+//! decision is recorded in a report.
+
+use log::{debug, warn};
+use serde::Serialize;
+
+use std::{collections::BTreeSet, fs::File};
+
+use crate::{
+    chord::{Chord, ChordSeqItem, ChordSequence},
+    generator::Generator,
+    ErrBox,
+};
+
+/// The chord appended when nothing else resolves a conflict. No
+/// built-in table entry or combo presses both keys.
+pub const DEFAULT_DISAMBIGUATION_STROKE: &str = "~*";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResolutionAction {
+    /// The word keeps the contested outline
+    Kept,
+    /// Another candidate outline was free
+    Alternative,
+    AddedAsterisk,
+    AddedTilde,
+    DisambiguationStroke,
+    /// Nothing worked, the word still shares its outline
+    Unresolved,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ConflictDecision {
+    pub word: String,
+    pub original: String,
+    pub resolved: String,
+    pub action: ResolutionAction,
+    pub frequency: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ConflictReport {
+    pub decisions: Vec<ConflictDecision>,
+}
+
+impl ConflictReport {
+    pub fn count(&self, action: ResolutionAction) -> usize {
+        self.decisions.iter().filter(|d| d.action == action).count()
+    }
+
+    pub fn save(&self, f: File) -> Result<(), ErrBox> {
+        serde_json::to_writer_pretty(f, self)?;
+        Ok(())
+    }
+}
+
+pub struct ConflictResolver {
+    /// Look for a different, free candidate outline first
+    pub try_alternatives: bool,
+    pub use_asterisk: bool,
+    pub use_tilde: bool,
+    /// Appended as the last resort
+    pub disambiguation_stroke: Option<Chord>,
+    /// More frequent word roots keep the contested outline and get
+    /// the first pick of alternatives
+    pub prefer_frequent: bool,
+}
+
+impl Default for ConflictResolver {
+    fn default() -> Self {
+        Self {
+            try_alternatives: true,
+            use_asterisk: true,
+            use_tilde: true,
+            disambiguation_stroke: Some(
+                DEFAULT_DISAMBIGUATION_STROKE
+                    .parse()
+                    .expect("default disambiguation stroke must parse"),
+            ),
+            prefer_frequent: true,
+        }
+    }
+}
+
+impl ConflictResolver {
+    /// Give every word in gen.word_root_conflict_dict a unique outline
+    pub fn resolve(&self, gen: &mut Generator) -> ConflictReport {
+        let mut report = ConflictReport::default();
+
+        let conflicts: Vec<(ChordSequence, BTreeSet<String>)> = gen
+            .word_root_conflict_dict
+            .iter()
+            .filter(|(_outline, words)| words.len() > 1)
+            .map(|(outline, words)| (outline.clone(), words.clone()))
+            .collect();
+
+        let mut taken = gen.used_outlines();
+
+        for (outline, words) in conflicts {
+            let mut words: Vec<String> = words.into_iter().collect();
+
+            // Most frequent first, shorter words win ties
+            words.sort_by(|a, b| {
+                let by_frequency = if self.prefer_frequent {
+                    gen.root_frequency(b).cmp(&gen.root_frequency(a))
+                } else {
+                    std::cmp::Ordering::Equal
+                };
+
+                by_frequency
+                    .then_with(|| a.chars().count().cmp(&b.chars().count()))
+                    .then_with(|| a.cmp(b))
+            });
+
+            let mut words_iter = words.into_iter();
+
+            if let Some(keeper) = words_iter.next() {
+                debug!("CONFLICT KEEP:\t{} ({})", keeper, outline.print_chords());
+                report.decisions.push(ConflictDecision {
+                    frequency: gen.root_frequency(&keeper),
+                    word: keeper,
+                    original: outline.print_chords(),
+                    resolved: outline.print_chords(),
+                    action: ResolutionAction::Kept,
+                });
+            }
+
+            for word in words_iter {
+                let (resolved, action) = match self.find_free_outline(gen, &word, &outline, &taken)
+                {
+                    Some((resolved, action)) => {
+                        taken.insert(resolved.collapse());
+                        gen.reassign_word_root(&word, &outline, resolved.clone());
+                        (resolved, action)
+                    }
+                    None => {
+                        warn!(
+                            "CONFLICT UNRESOLVED:\t{} ({})",
+                            word,
+                            outline.print_chords()
+                        );
+                        (outline.clone(), ResolutionAction::Unresolved)
+                    }
+                };
+
+                debug!(
+                    "CONFLICT {:?}:\t{} ({} -> {})",
+                    action,
+                    word,
+                    outline.print_chords(),
+                    resolved.print_chords()
+                );
+
+                report.decisions.push(ConflictDecision {
+                    frequency: gen.root_frequency(&word),
+                    word,
+                    original: outline.print_chords(),
+                    resolved: resolved.print_chords(),
+                    action,
+                });
+            }
+        }
+
+        report
+    }
+
+    fn find_free_outline(
+        &self,
+        gen: &Generator,
+        word: &str,
+        outline: &ChordSequence,
+        taken: &BTreeSet<Vec<Chord>>,
+    ) -> Option<(ChordSequence, ResolutionAction)> {
+        let is_free = |chords: &ChordSequence| !taken.contains(&chords.collapse());
+
+        if self.try_alternatives {
+            // Only root-only outlines can stand in for a word root
+            let alternative = gen
+                .gen_word_candidates(word, usize::MAX)
+                .ok()
+                .into_iter()
+                .flatten()
+                .find(|c| !c.score.uses_affixes && is_free(&c.chords));
+
+            if let Some(candidate) = alternative {
+                return Some((candidate.chords, ResolutionAction::Alternative));
+            }
+        }
+
+        let modifiers = [
+            (self.use_asterisk, "*", ResolutionAction::AddedAsterisk),
+            (self.use_tilde, "~", ResolutionAction::AddedTilde),
+        ];
+
+        for (enabled, key, action) in modifiers {
+            if !enabled {
+                continue;
+            }
+
+            let key_chord: Chord = key.parse().ok()?;
+
+            if let Some(modified) = with_last_stroke_merged(outline, &key_chord) {
+                if is_free(&modified) {
+                    return Some((modified, action));
+                }
+            }
+        }
+
+        if let Some(stroke) = self.disambiguation_stroke {
+            let mut appended = outline.clone();
+            appended.items.push(ChordSeqItem::Disambiguation(stroke));
+
+            if is_free(&appended) {
+                return Some((appended, ResolutionAction::DisambiguationStroke));
+            }
+        }
+
+        None
+    }
+}
+
+/// Press extra keys on the last stroke of outline, if they are free and
+/// the resulting chord is valid
+fn with_last_stroke_merged(outline: &ChordSequence, extra: &Chord) -> Option<ChordSequence> {
+    let mut modified = outline.clone();
+
+    match modified.items.last_mut()? {
+        ChordSeqItem::RootChord(_, ch)
+        | ChordSeqItem::Prefix(_, ch)
+        | ChordSeqItem::Suffix(_, ch)
+        | ChordSeqItem::Infix(_, ch)
+        | ChordSeqItem::Disambiguation(ch) => {
+            ch.merge(extra).ok()?;
+        }
+    }
+
+    Some(modified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{dict_lookup, theory::Theory};
+
+    #[test]
+    fn test_conflicting_words_get_unique_outlines() -> Result<(), ErrBox> {
+        let mut gen = Generator::new(Theory::builtin())?;
+        gen.word_frequencies.insert("tit".to_string(), 10);
+
+        // Same root outline, different words
+        gen.add_word_root("cit")?;
+        gen.add_word_root("tit")?;
+
+        let conflicting: Vec<_> = gen
+            .word_root_conflict_dict
+            .values()
+            .filter(|words| words.len() > 1)
+            .collect();
+        assert!(!conflicting.is_empty());
+
+        let report = ConflictResolver::default().resolve(&mut gen);
+
+        assert_eq!(report.count(ResolutionAction::Unresolved), 0);
+        assert!(gen
+            .word_root_conflict_dict
+            .values()
+            .all(|words| words.len() <= 1));
+
+        // The more frequent word keeps the original outline
+        let kept = report
+            .decisions
+            .iter()
+            .find(|d| d.action == ResolutionAction::Kept)
+            .unwrap();
+        assert_eq!(kept.word, "tit");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_disambiguation_stroke_collides_with_nothing() -> Result<(), ErrBox> {
+        let stroke: Chord = DEFAULT_DISAMBIGUATION_STROKE.parse()?;
+        stroke.validate()?;
+
+        let mut chords: Vec<Chord> = vec![
+            dict_lookup::NULL_INFIX.parse()?,
+            dict_lookup::SPACING_INFIX.parse()?,
+        ];
+
+        for (section, table) in Theory::builtin().tables() {
+            for chord in table.values() {
+                let parsed: Result<Chord, _> = match section {
+                    "right_hand_combos" => format!("-{}", chord).parse(),
+                    _other => chord.parse(),
+                };
+
+                // Unparseable entries are the lint's business
+                chords.extend(parsed.ok());
+            }
+        }
+
+        // Not even part of a combo, so no chunk can press it either
+        for chord in chords {
+            assert!(!chord.contains(&stroke), "{}", chord.to_string());
+        }
+
+        Ok(())
+    }
+}
@@ -1511,6 +1511,19 @@ pub static PREFIXES: phf::Map<&'static str, &'static str> = phf::phf_map! {
 pub static NULL_INFIX: &'static str = "XF-OY"; // Base for other infixes
 pub static SPACING_INFIX: &'static str = "XF*OY"; // Forces space, e.g. if you make a prefix by mistake where you wanted a preposition
 
+// Interfixes joining the parts of a compound word, written as
+// NULL_INFIX plus the keys of the joining vowel (see CENTER_COMBOS) and
+// ~ for a hyphen. The bare NULL_INFIX joins two parts with nothing in
+// between.
+pub static INFIXES: phf::Map<&'static str, &'static str> = phf::phf_map! {
+    "" => NULL_INFIX,
+    "o" => "XFAUOY", // wod-o-ciąg, dług-o-pis
+    "i" => "XFIOY", // pięc-i-oletni
+    "y" => "XFIAUOY", // wiar-y-godny, cudz-y-słów
+    "-" => "XF~OY", // polsko-niemiecki
+    "o-" => "XF~AUOY", // biał-o-czerwony
+};
+
 pub static SUFFIXES: phf::Map<&'static str, &'static str> = phf::phf_map! {
     // page 154, 155, 156, 157, 158
     "a" => "ZKPLA",
@@ -2511,16 +2524,134 @@ pub static COMMANDS: phf::Map<&'static str, &'static str> = phf::phf_map! {
     "{-|}" => "~O",
 };
 
+// Grammatical constraints on SUFFIXES entries. A suffix listed here
+// is only stripped from words with an analysis matching one of its
+// constraints: a part of speech, optionally followed by tag values,
+// e.g. "subst:pl". Suffixes not listed here and words the morphology
+// doesn't know are unconstrained.
+pub static SUFFIX_CONSTRAINTS: phf::Map<&'static str, &'static [&'static str]> = phf::phf_map! {
+    // Adjectival declension, nouns only for plural forms
+    "ych" => &["adj", "pact", "ppas", "num", "subst:pl"],
+    "ymi" => &["adj", "pact", "ppas", "num", "subst:pl"],
+    "ich" => &["adj", "pact", "ppas", "num", "subst:pl"],
+    "imi" => &["adj", "pact", "ppas", "num", "subst:pl"],
+    "nych" => &["adj", "pact", "ppas", "num", "subst:pl"],
+    "nymi" => &["adj", "pact", "ppas", "num", "subst:pl"],
+    "czych" => &["adj", "pact", "ppas", "num", "subst:pl"],
+    "czymi" => &["adj", "pact", "ppas", "num", "subst:pl"],
+    "szych" => &["adj", "pact", "ppas", "num", "subst:pl"],
+    "szymi" => &["adj", "pact", "ppas", "num", "subst:pl"],
+    "ckich" => &["adj", "pact", "ppas", "num", "subst:pl"],
+    "ckimi" => &["adj", "pact", "ppas", "num", "subst:pl"],
+    "skich" => &["adj", "pact", "ppas", "num", "subst:pl"],
+    "skimi" => &["adj", "pact", "ppas", "num", "subst:pl"],
+
+    // Adjectival declension, nouns only for the matching case
+    "ego" => &["adj", "pact", "ppas", "num", "subst:sg:gen", "subst:sg:acc"],
+    "iego" => &["adj", "pact", "ppas", "num", "subst:sg:gen", "subst:sg:acc"],
+    "nego" => &["adj", "pact", "ppas", "num", "subst:sg:gen", "subst:sg:acc"],
+    "czego" => &["adj", "pact", "ppas", "num", "subst:sg:gen", "subst:sg:acc"],
+    "szego" => &["adj", "pact", "ppas", "num", "subst:sg:gen", "subst:sg:acc"],
+    "ckiego" => &["adj", "pact", "ppas", "num", "subst:sg:gen", "subst:sg:acc"],
+    "skiego" => &["adj", "pact", "ppas", "num", "subst:sg:gen", "subst:sg:acc"],
+    "emu" => &["adj", "pact", "ppas", "num", "subst:sg:dat"],
+    "iemu" => &["adj", "pact", "ppas", "num", "subst:sg:dat"],
+    "nemu" => &["adj", "pact", "ppas", "num", "subst:sg:dat"],
+    "czemu" => &["adj", "pact", "ppas", "num", "subst:sg:dat"],
+    "szemu" => &["adj", "pact", "ppas", "num", "subst:sg:dat"],
+    "ckiemu" => &["adj", "pact", "ppas", "num", "subst:sg:dat"],
+    "skiemu" => &["adj", "pact", "ppas", "num", "subst:sg:dat"],
+
+    // Infinitives
+    "ać" => &["inf"],
+    "eć" => &["inf"],
+    "ić" => &["inf"],
+    "ieć" => &["inf"],
+    "ować" => &["inf"],
+    "nąć" => &["inf"],
+    "ujć" => &["inf"],
+
+    // Past tense, e.g. "mały" and "ciało" are not "-ały"/"-ało"
+    "ał" => &["praet"],
+    "ało" => &["praet"],
+    "ały" => &["praet"],
+    "ali" => &["praet"],
+    "ił" => &["praet"],
+    "iło" => &["praet"],
+    "iły" => &["praet"],
+    "ili" => &["praet"],
+    "ył" => &["praet"],
+    "yło" => &["praet"],
+    "yły" => &["praet"],
+    "yli" => &["praet"],
+    "eł" => &["praet"],
+    "eło" => &["praet"],
+    "ely" => &["praet"],
+    "eli" => &["praet"],
+    "ował" => &["praet"],
+    "owało" => &["praet"],
+    "owały" => &["praet"],
+    "nął" => &["praet"],
+    "nąli" => &["praet"],
+    "nęł" => &["praet"],
+    "nęło" => &["praet"],
+    "nęły" => &["praet"],
+    "nęli" => &["praet"],
+
+    // Present tense and imperative, e.g. "mysz" is not "-ysz"
+    "asz" => &["fin"],
+    "esz" => &["fin"],
+    "isz" => &["fin"],
+    "ysz" => &["fin"],
+    "ajesz" => &["fin"],
+    "ejesz" => &["fin"],
+    "iesz" => &["fin"],
+    "ujesz" => &["fin"],
+    "niesz" => &["fin"],
+    "ają" => &["fin"],
+    "eją" => &["fin"],
+    "ują" => &["fin"],
+    "amy" => &["fin", "impt"],
+    "emy" => &["fin", "impt"],
+    "imy" => &["fin", "impt"],
+    "ymy" => &["fin", "impt"],
+    "ajemy" => &["fin", "impt"],
+    "ejemy" => &["fin", "impt"],
+    "iemy" => &["fin", "impt"],
+    "ujemy" => &["fin", "impt"],
+    "niemy" => &["fin", "impt"],
+    "acie" => &["fin", "impt"],
+    "ecie" => &["fin", "impt"],
+    "icie" => &["fin", "impt"],
+    "ycie" => &["fin", "impt"],
+    "ajecie" => &["fin", "impt"],
+    "ejecie" => &["fin", "impt"],
+    "iecie" => &["fin", "impt"],
+    "ujecie" => &["fin", "impt"],
+    "niecie" => &["fin", "impt"],
+
+    // Participles
+    "ąc" => &["pcon"],
+    "iąc" => &["pcon"],
+    "nąc" => &["pcon"],
+    "ący" => &["pact"],
+    "iący" => &["pact"],
+    "awszy" => &["pant"],
+};
+
 // Contains words like "pralina" which shouldn't use "pra-" like "pradziadek"
 pub static PREFIX_EXCEPTIONS: phf::Set<&'static str> = phf::phf_set! {
     "pralina",
 };
 
-// Contains words like "przepych" which shouldn't use "-ych" like "biernych"
+// Contains words like "przepych" which shouldn't use "-ych" like "biernych".
+// Only needed for words without a morphological analysis, otherwise
+// SUFFIX_CONSTRAINTS takes care of them.
 pub static SUFFIX_EXCEPTIONS: phf::Set<&'static str> = phf::phf_set! {
     "przepych"
 };
 
-pub static SJP_DICT: &'static str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/odm.txt"));
+#[cfg(feature = "embedded-odm")]
+pub static SJP_DICT: &'static str = include_str!("../odm.txt");
 
 pub static PL_DIACRITICS: &'static str = "ąćęłńóśźż";
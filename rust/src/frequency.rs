@@ -0,0 +1,55 @@
+//! Word frequency lists in the `word<TAB>count` format, e.g. counted
+//! from a local corpus. Lines starting with `#` are comments.
+
+use std::{collections::BTreeMap, io::BufRead};
+
+use crate::ErrBox;
+
+/// Load a frequency list. Words are lowercased and counts for repeated
+/// words are summed up.
+pub fn load_frequencies(r: impl BufRead) -> Result<BTreeMap<String, u64>, ErrBox> {
+    let mut ret = BTreeMap::new();
+
+    for (idx, line) in r.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (word, count) = line
+            .split_once('\t')
+            .ok_or_else(|| format!("line {}: expected word<TAB>count, got {:?}", idx + 1, line))?;
+
+        let count: u64 = count
+            .trim()
+            .parse()
+            .map_err(|e| format!("line {}: bad count {:?}: {}", idx + 1, count, e))?;
+
+        *ret.entry(word.trim().to_lowercase()).or_insert(0) += count;
+    }
+
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_frequencies() -> Result<(), ErrBox> {
+        let input = "# corpus counts\nnie\t120\nKot\t3\n\nkot\t2\n";
+
+        let freqs = load_frequencies(input.as_bytes())?;
+
+        assert_eq!(freqs.get("nie"), Some(&120));
+        assert_eq!(freqs.get("kot"), Some(&5));
+        assert_eq!(freqs.len(), 2);
+
+        assert!(load_frequencies("kot 3\n".as_bytes()).is_err());
+        assert!(load_frequencies("kot\tdużo\n".as_bytes()).is_err());
+
+        Ok(())
+    }
+}
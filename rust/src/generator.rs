@@ -5,18 +5,112 @@ use regex::Regex;
 use std::{
     collections::{BTreeMap, BTreeSet},
     fs::File,
+    io::Write,
 };
 
 use crate::{
-    chord::{Chord, ChordSeqItem, ChordSequence},
+    chord::{Chord, ChordSeqItem, ChordSequence, Side},
     dict_lookup,
+    morphology::{Analysis, Morphology},
+    output::{self, DictEntry, DictFormat},
+    provenance::{ComboRef, EntrySource, ProvenanceReport, RootProvenance, RootSource},
+    rtf::{self, RtfReport},
+    syllables,
+    theory::Theory,
     utils::LenSortableString,
     ErrBox,
 };
 
+/// Affixes are never stripped down to a shorter word root
+const MIN_ROOT_LEN: usize = 2;
+
+/// Compounds are only split into parts at least this long
+const MIN_COMPOUND_PART_LEN: usize = 3;
+
+/// How a chunk is reduced to a sequence of strokes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChunkStrategy {
+    /// Single pass taking the longest left-hand, center and right-hand
+    /// match, starting a new stroke on the first conflict
+    Greedy,
+    /// Tries every segmentation of the chunk and keeps the outline with
+    /// the fewest strokes
+    #[default]
+    Backtracking,
+}
+
+/// How a word root is split into chunks before they are turned into
+/// strokes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SplitStrategy {
+    /// syllable_split(), moving at most one consonant to the next
+    /// syllable
+    #[default]
+    Rough,
+    /// syllables::syllabify(), giving the next syllable the longest
+    /// permissible onset
+    MaximalOnset,
+    /// One chunk per stroke, see stroke_aware_split()
+    StrokeAware,
+}
+
+/// How good a candidate outline is. Lower is better: fewer strokes
+/// first, then fewer keys, then whole-root shortcuts and affix splits
+/// are preferred over plain chunk generation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutlineScore {
+    pub strokes: usize,
+    pub keys: u32,
+    pub uses_affixes: bool,
+    pub whole_root: bool,
+}
+
+impl OutlineScore {
+    fn rank_key(&self) -> (usize, u32, bool, bool) {
+        (self.strokes, self.keys, !self.whole_root, !self.uses_affixes)
+    }
+}
+
+impl PartialOrd for OutlineScore {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OutlineScore {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.rank_key().cmp(&other.rank_key())
+    }
+}
+
+#[derive(Clone)]
+pub struct OutlineCandidate {
+    pub chords: ChordSequence,
+    /// Chunks this candidate would add to the chunk dictionary
+    pub new_chunks: Vec<ChordSequence>,
+    pub score: OutlineScore,
+}
+
 pub struct Generator {
+    pub theory: Theory,
+    pub chunk_strategy: ChunkStrategy,
+    pub split_strategy: SplitStrategy,
+    /// Corpus counts per word, see frequency::load_frequencies()
+    pub word_frequencies: BTreeMap<String, u64>,
+    /// Words at least this frequent take their best free candidate
+    /// outline instead of the default one
+    pub frequent_word_threshold: Option<u64>,
+    /// Summed up frequencies of all words sharing a word root
+    pub root_frequencies: BTreeMap<String, u64>,
+    /// How many prefixes and suffixes may be stacked on a word root
+    pub max_prefixes: usize,
+    pub max_suffixes: usize,
+    /// Split unhyphenated compounds at INFIXES vowels when both parts
+    /// are known word roots. Hyphenated words are always split.
+    pub split_compounds: bool,
     prefixes_len_sorted: BTreeMap<LenSortableString<false>, Chord>,
     suffixes_len_sorted: BTreeMap<LenSortableString<false>, Chord>,
+    infixes_len_sorted: BTreeMap<LenSortableString<false>, Chord>,
     lh_combos_len_sorted: BTreeMap<LenSortableString<false>, Chord>,
     center_combos_len_sorted: BTreeMap<LenSortableString<false>, Chord>,
     rh_combos_len_sorted: BTreeMap<LenSortableString<false>, Chord>,
@@ -24,46 +118,34 @@ pub struct Generator {
     pub word_root_conflict_dict: BTreeMap<ChordSequence, BTreeSet<String>>,
     pub chunk_dict: BTreeMap<LenSortableString<false>, ChordSequence>,
     pub chunk_conflict_dict: BTreeMap<ChordSequence, BTreeSet<String>>,
+    /// Complete outlines of words which were not split by
+    /// gen_word_chords(), e.g. forms of a lexeme sharing one root
+    pub word_outlines: BTreeMap<String, ChordSequence>,
+    /// How each word root in word_root_dict was made
+    pub root_provenance: BTreeMap<String, RootProvenance>,
+    /// Lemmas and tags for words, if any backend is available
+    pub morphology: Option<Box<dyn Morphology>>,
 }
 
 impl Generator {
-    pub fn new() -> Result<Self, ErrBox> {
+    pub fn new(theory: Theory) -> Result<Self, ErrBox> {
         // In the subsequent steps, we intend to match words against the
         // longest available affixes. Here we prepare prefixes and
         // suffixes sorted by descending length for that purpose.
-        let prefixes_len_sorted: BTreeMap<LenSortableString<false>, Chord> = dict_lookup::PREFIXES
-            .into_iter()
-            .map(|(txt, chord)| chord.parse().map(|ch| ((*txt).into(), ch)))
-            .collect::<Result<_, _>>()?;
-
-        let suffixes_len_sorted: BTreeMap<LenSortableString<false>, Chord> = dict_lookup::SUFFIXES
-            .into_iter()
-            .map(|(txt, chord)| chord.parse().map(|ch| ((*txt).into(), ch)))
-            .collect::<Result<_, _>>()?;
+        let prefixes_len_sorted = parse_table(&theory.prefixes, "")?;
+        let suffixes_len_sorted = parse_table(&theory.suffixes, "")?;
+        let infixes_len_sorted = parse_table(&theory.infixes, "")?;
 
         //  With the left/center/right combos the story is similar. We
         //  wish to match against the longest available word part
-        let lh_combos_len_sorted: BTreeMap<LenSortableString<false>, Chord> =
-            dict_lookup::LEFT_HAND_COMBOS
-                .into_iter()
-                .map(|(txt, chord)| chord.parse().map(|ch| ((*txt).into(), ch)))
-                .collect::<Result<_, _>>()?;
-
-        let center_combos_len_sorted: BTreeMap<LenSortableString<false>, Chord> =
-            dict_lookup::CENTER_COMBOS
-                .into_iter()
-                .map(|(txt, chord)| chord.parse().map(|ch| ((*txt).into(), ch)))
-                .collect::<Result<_, _>>()?;
-
-        let rh_combos_len_sorted: BTreeMap<LenSortableString<false>, Chord> =
-            dict_lookup::RIGHT_HAND_COMBOS
-                .into_iter()
-                .map(|(txt, chord)| format!("-{}", chord).parse().map(|ch| ((*txt).into(), ch)))
-                .collect::<Result<_, _>>()?;
+        let lh_combos_len_sorted = parse_table(&theory.left_hand_combos, "")?;
+        let center_combos_len_sorted = parse_table(&theory.center_combos, "")?;
+        let rh_combos_len_sorted = parse_table(&theory.right_hand_combos, "-")?;
 
         let word_root_dict: BTreeMap<LenSortableString<false>, ChordSequence> =
-            dict_lookup::SHORTCUTS
-                .into_iter()
+            theory
+                .shortcuts
+                .iter()
                 .map(|(word, chord)| {
                     chord.parse::<Chord>().map(|ch| {
                         (
@@ -74,11 +156,32 @@ impl Generator {
                 })
                 .collect::<Result<_, _>>()?;
 
+        let root_provenance = theory
+            .shortcuts
+            .keys()
+            .map(|word| {
+                (
+                    word.clone(),
+                    RootProvenance::new(RootSource::Shortcut, word),
+                )
+            })
+            .collect();
+
         let chunk_dict: BTreeMap<LenSortableString<false>, ChordSequence> = Default::default();
 
         Ok(Self {
+            theory,
+            chunk_strategy: ChunkStrategy::default(),
+            split_strategy: SplitStrategy::default(),
+            word_frequencies: BTreeMap::new(),
+            frequent_word_threshold: None,
+            root_frequencies: BTreeMap::new(),
+            max_prefixes: 1,
+            max_suffixes: 1,
+            split_compounds: false,
             prefixes_len_sorted,
             suffixes_len_sorted,
+            infixes_len_sorted,
             lh_combos_len_sorted,
             center_combos_len_sorted,
             rh_combos_len_sorted,
@@ -86,24 +189,128 @@ impl Generator {
             word_root_conflict_dict: BTreeMap::new(),
             chunk_dict,
             chunk_conflict_dict: BTreeMap::new(),
+            word_outlines: BTreeMap::new(),
+            root_provenance,
+            morphology: None,
         })
     }
 
     /// NOTE: Only root recipe is added to the dictionary, but the
     /// complete chord set is returned.
     pub fn add_word_root(&mut self, word: &str) -> Result<ChordSequence, ErrBox> {
-        let (word_chords, new_chunk_chords) = self.gen_word_chords(word)?;
-        let mut root_chords = word_chords.clone();
-        root_chords.items = word_chords
+        let frequency = self.frequency(word);
+
+        let (word_chords, new_chunk_chords) = match self.frequent_word_threshold {
+            Some(threshold) if frequency >= threshold => self.gen_frequent_word_chords(word)?,
+            _other => self.gen_word_chords(word)?,
+        };
+
+        self.record_word_chords(word, &word_chords, new_chunk_chords, frequency);
+
+        Ok(word_chords)
+    }
+
+    /// Add word with an outline decided elsewhere. Its root and chunks
+    /// are recorded like in add_word_root(), and word_outline() returns
+    /// chords for it from now on.
+    pub fn add_word_with_outline(
+        &mut self,
+        word: &str,
+        word_chords: ChordSequence,
+        new_chunk_chords: Vec<ChordSequence>,
+    ) {
+        let frequency = self.frequency(word);
+
+        self.record_word_chords(word, &word_chords, new_chunk_chords, frequency);
+
+        self.word_outlines.insert(word.to_string(), word_chords);
+    }
+
+    fn record_word_chords(
+        &mut self,
+        word: &str,
+        word_chords: &ChordSequence,
+        new_chunk_chords: Vec<ChordSequence>,
+        frequency: u64,
+    ) {
+        // Parts of a compound are word roots of their own
+        let parts = compound_parts(word_chords);
+        let is_compound = parts.len() > 1;
+
+        for part in parts {
+            self.record_root_provenance(word, &part, is_compound);
+            self.record_root_chords(root_only(&part), frequency);
+        }
+
+        for chunk in new_chunk_chords {
+            self.chunk_dict
+                .insert(chunk.get_word().into(), chunk.clone());
+
+            if let Some(existing) = self.chunk_conflict_dict.get_mut(&chunk) {
+                trace!(
+                    "CHUNK-CONFLICT Stroke(s) {} already exist for: {:?}",
+                    chunk.print_chords(),
+                    existing
+                );
+                existing.insert(chunk.get_word());
+            } else {
+                let mut new_set = BTreeSet::new();
+                new_set.insert(chunk.get_word());
+                self.chunk_conflict_dict.insert(chunk.clone(), new_set);
+            }
+        }
+    }
+
+    /// Count an exact-root hit, or note how a new word root was made
+    fn record_root_provenance(&mut self, word: &str, part: &ChordSequence, is_compound: bool) {
+        let root = root_only(part).get_word();
+
+        if root.is_empty() {
+            return;
+        }
+
+        if let Some(existing) = self.root_provenance.get_mut(&root) {
+            existing.exact_root_hits += 1;
+            return;
+        }
+
+        let affixes: Vec<String> = part
             .items
             .iter()
             .filter_map(|item| match item {
-                &ChordSeqItem::RootChord(_, _) => Some(item),
+                ChordSeqItem::Prefix(s, _ch) => Some(format!("{}-", s)),
+                ChordSeqItem::Suffix(s, _ch) => Some(format!("-{}", s)),
                 _other => None,
             })
-            .cloned()
             .collect();
 
+        let source = if is_compound {
+            RootSource::Compound
+        } else if affixes.is_empty() {
+            RootSource::Chunks
+        } else {
+            RootSource::AffixStripping
+        };
+
+        self.root_provenance.insert(
+            root,
+            RootProvenance {
+                affixes,
+                ..RootProvenance::new(source, word)
+            },
+        );
+    }
+
+    fn record_root_chords(&mut self, root_chords: ChordSequence, frequency: u64) {
+        if root_chords.items.is_empty() {
+            return;
+        }
+
+        *self
+            .root_frequencies
+            .entry(root_chords.get_word())
+            .or_insert(0) += frequency;
+
         self.word_root_dict
             .insert(root_chords.get_word().into(), root_chords.clone());
 
@@ -121,123 +328,743 @@ impl Generator {
             self.word_root_conflict_dict
                 .insert(root_chords.clone(), new_set);
         }
+    }
 
-        for chunk in new_chunk_chords {
-            self.chunk_dict
-                .insert(chunk.get_word().into(), chunk.clone());
+    pub fn frequency(&self, word: &str) -> u64 {
+        self.word_frequencies
+            .get(&word.trim().to_lowercase())
+            .cloned()
+            .unwrap_or(0)
+    }
 
-            if let Some(existing) = self.chunk_conflict_dict.get_mut(&chunk) {
-                trace!(
-                    "CHUNK-CONFLICT Stroke(s) {} already exist for: {:?}",
-                    chunk.print_chords(),
-                    existing
+    pub fn root_frequency(&self, word_root: &str) -> u64 {
+        self.root_frequencies.get(word_root).cloned().unwrap_or(0)
+    }
+
+    /// Frequent words get the shortest candidate whose root outline is
+    /// still free, falling back to the default outline.
+    fn gen_frequent_word_chords(
+        &self,
+        word: &str,
+    ) -> Result<(ChordSequence, Vec<ChordSequence>), ErrBox> {
+        for candidate in self.gen_word_candidates(word, 10)? {
+            if !self.is_root_outline_taken(&candidate.chords) {
+                debug!("FREQUENT WORD:\t{} ({})", word, candidate.chords.to_string());
+                return Ok((candidate.chords, candidate.new_chunks));
+            }
+        }
+
+        self.gen_word_chords(word)
+    }
+
+    /// Generate
+    pub fn gen_word_chords(
+        &self,
+        word: &str,
+    ) -> Result<(ChordSequence, Vec<ChordSequence>), ErrBox> {
+        self.gen_chords_with_affixes(word, true)
+    }
+
+    /// Like gen_word_chords(), but the stem is never stripped of a
+    /// suffix. Inflection endings are attached to the result by the caller.
+    pub fn gen_stem_chords(
+        &self,
+        stem: &str,
+    ) -> Result<(ChordSequence, Vec<ChordSequence>), ErrBox> {
+        self.gen_chords_with_affixes(stem, false)
+    }
+
+    /// All analyses of word, none without a morphology
+    fn analyse(&self, word: &str) -> Result<Vec<Analysis>, ErrBox> {
+        match &self.morphology {
+            Some(morphology) => morphology.analyse(word),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Prefixes which may be stripped off word, longest first. None
+    /// for PREFIX_EXCEPTIONS, and none which would leave a root shorter
+    /// than MIN_ROOT_LEN.
+    fn prefix_options(&self, word: &str) -> Vec<(String, Chord)> {
+        if self.theory.prefix_exceptions.contains(word) {
+            trace!("SKIP PREFIX EXCEPTION");
+            return vec![];
+        }
+
+        find_all_affixes(word, &self.prefixes_len_sorted, 2, true)
+            .into_iter()
+            .filter(|(pref_str, _chord)| {
+                word.chars().count() - pref_str.chars().count() >= MIN_ROOT_LEN
+            })
+            .collect()
+    }
+
+    /// Same as prefix_options() for suffixes, which also have to be
+    /// allowed for analyses
+    fn suffix_options(&self, word: &str, analyses: &[Analysis]) -> Vec<(String, Chord)> {
+        if self.theory.suffix_exceptions.contains(word) {
+            trace!("SKIP SUFFIX EXCEPTION");
+            return vec![];
+        }
+
+        find_all_affixes(word, &self.suffixes_len_sorted, 2, false)
+            .into_iter()
+            .filter(|(suff_str, _chord)| {
+                word.chars().count() - suff_str.chars().count() >= MIN_ROOT_LEN
+            })
+            .filter(|(suff_str, _chord)| self.is_suffix_allowed(suff_str, analyses))
+            .collect()
+    }
+
+    /// Whether suffix may be stripped from a word with analyses.
+    /// Suffixes without constraints and unknown words are always
+    /// allowed.
+    fn is_suffix_allowed(&self, suffix: &str, analyses: &[Analysis]) -> bool {
+        let Some(constraints) = self.theory.suffix_constraints.get(suffix) else {
+            return true;
+        };
+
+        if analyses.is_empty() {
+            return true;
+        }
+
+        let allowed = analyses
+            .iter()
+            .any(|a| constraints.iter().any(|c| a.matches(c)));
+
+        if !allowed {
+            debug!("SKIP SUFFIX CONSTRAINT:\t-{} {:?}", suffix, constraints);
+        }
+
+        allowed
+    }
+
+    pub fn suffix_chord(&self, suffix: &str) -> Option<Chord> {
+        self.suffixes_len_sorted
+            .get(&suffix.to_string().into())
+            .cloned()
+    }
+
+    fn gen_chords_with_affixes(
+        &self,
+        word: &str,
+        strip_suffix: bool,
+    ) -> Result<(ChordSequence, Vec<ChordSequence>), ErrBox> {
+        let word = sanitize_word(word)?;
+
+        debug!("WORD: {}", word);
+
+        if let Some(compound) = self.gen_compound_chords(&word, strip_suffix)? {
+            return Ok(compound);
+        }
+
+        let analyses = self.analyse(&word)?;
+
+        let mut word_root = word.clone();
+
+        let mut prefixes: Vec<ChordSeqItem> = Vec::new();
+
+        trace!("ATTEMPT PREFIX");
+        // Longest prefix first, as long as the root allows it
+        while prefixes.len() < self.max_prefixes {
+            let Some((pref_str, pref_chord)) = self.prefix_options(&word_root).into_iter().next()
+            else {
+                break;
+            };
+
+            debug!("REDUCE PREFIX:\t{}-", pref_str);
+            word_root = word_root.strip_prefix(&pref_str).unwrap().to_string();
+            prefixes.push(ChordSeqItem::Prefix(pref_str, pref_chord));
+        }
+
+        // Outermost suffix first
+        let mut suffixes: Vec<ChordSeqItem> = Vec::new();
+
+        trace!("ATTEMPT SUFFIX");
+        if !strip_suffix {
+            trace!("SKIP SUFFIX FOR STEM");
+        } else {
+            // Longest suffix allowed for this word
+            while suffixes.len() < self.max_suffixes {
+                let Some((suff_str, suff_chord)) = self
+                    .suffix_options(&word_root, &analyses)
+                    .into_iter()
+                    .next()
+                else {
+                    break;
+                };
+
+                debug!("REDUCE SUFFIX:\t-{}", suff_str,);
+                word_root = word_root.strip_suffix(&suff_str).unwrap().to_string();
+                suffixes.push(ChordSeqItem::Suffix(suff_str, suff_chord));
+            }
+        }
+
+        let (root_chords, new_chunks) =
+            if let Some(chords) = self.word_root_dict.get(&word_root.clone().into()).cloned() {
+                debug!("SKIP EXACT-ROOT:\t{} ({})", word_root, chords.to_string());
+                (chords.items, vec![])
+            } else {
+                self.gen_root_chunk_chords(&word_root)?
+            };
+
+        let chords: Vec<_> = prefixes
+            .into_iter()
+            .chain(root_chords)
+            .chain(suffixes.into_iter().rev())
+            .collect();
+
+        Ok((ChordSequence::new(chords), new_chunks))
+    }
+
+    /// Outline of a compound word: its parts joined by an INFIXES
+    /// stroke. Hyphenated words are split at the first hyphen, taking
+    /// the vowel before it into the infix if there is one for it, e.g.
+    /// biał-o-czerwony. With split_compounds, other words are split at
+    /// an interfix vowel if both parts have known word roots, fewest
+    /// strokes first. None if word is not a compound.
+    fn gen_compound_chords(
+        &self,
+        word: &str,
+        strip_suffix: bool,
+    ) -> Result<Option<(ChordSequence, Vec<ChordSequence>)>, ErrBox> {
+        if let Some((left, right)) = word.split_once('-') {
+            let (left, infix) = find_longest_affix(left, &self.infixes_len_sorted, 1, false)
+                .and_then(|(vowel, _chord)| {
+                    let stem = left.strip_suffix(vowel.as_str())?;
+                    (stem.chars().count() >= MIN_ROOT_LEN).then(|| (stem, format!("{}-", vowel)))
+                })
+                .filter(|(_stem, infix)| {
+                    self.infixes_len_sorted.contains_key(&infix.clone().into())
+                })
+                .unwrap_or((left, "-".to_string()));
+
+            let infix_chord = self
+                .infixes_len_sorted
+                .get(&infix.clone().into())
+                .cloned()
+                .ok_or_else(|| format!("no infix for {:?} in {}", infix, word))?;
+
+            let (left_chords, mut new_chunks) = self.gen_chords_with_affixes(left, false)?;
+            let (right_chords, mut right_chunks) =
+                self.gen_chords_with_affixes(right, strip_suffix)?;
+            new_chunks.append(&mut right_chunks);
+
+            debug!("REDUCE COMPOUND:\t{} -{}- {}", left, infix, right);
+
+            return Ok(Some((
+                join_compound(left_chords, &infix, infix_chord, right_chords),
+                new_chunks,
+            )));
+        }
+
+        if !self.split_compounds || self.word_root_dict.contains_key(&word.to_string().into()) {
+            return Ok(None);
+        }
+
+        let mut best: Option<(ChordSequence, Vec<ChordSequence>)> = None;
+
+        for (infix, infix_chord) in self.infixes_len_sorted.iter() {
+            let infix = &infix.0;
+            if infix.is_empty() || infix.contains('-') {
+                continue;
+            }
+
+            for (idx, _match) in word.match_indices(infix.as_str()) {
+                let left = &word[..idx];
+                let right = &word[idx + infix.len()..];
+
+                if left.chars().count() < MIN_COMPOUND_PART_LEN
+                    || right.chars().count() < MIN_COMPOUND_PART_LEN
+                {
+                    continue;
+                }
+
+                // Either a word root itself or the root of a word ending
+                // in a vowel, like wod- for woda
+                let (left_chords, mut new_chunks) =
+                    match self.word_root_dict.get(&left.to_string().into()) {
+                        Some(chords) => (chords.clone(), vec![]),
+                        None if syllables::VOWELS.chars().any(|v| {
+                            self.word_root_dict
+                                .contains_key(&format!("{}{}", left, v).into())
+                        }) =>
+                        {
+                            match self.gen_chords_with_affixes(left, false) {
+                                Ok(generated) => generated,
+                                Err(_e) => continue,
+                            }
+                        }
+                        None => continue,
+                    };
+
+                let Ok((right_chords, mut right_chunks)) =
+                    self.gen_chords_with_affixes(right, strip_suffix)
+                else {
+                    continue;
+                };
+                new_chunks.append(&mut right_chunks);
+
+                if !self
+                    .word_root_dict
+                    .contains_key(&root_only(&right_chords).get_word().into())
+                {
+                    continue;
+                }
+
+                let chords = join_compound(left_chords, infix, *infix_chord, right_chords);
+
+                let is_better = match &best {
+                    Some((best_chords, _chunks)) => {
+                        chords.collapse().len() < best_chords.collapse().len()
+                    }
+                    None => true,
+                };
+
+                if is_better {
+                    debug!("REDUCE COMPOUND:\t{} -{}- {}", left, infix, right);
+                    best = Some((chords, new_chunks));
+                }
+            }
+        }
+
+        Ok(best)
+    }
+
+    /// Split word into chunks according to split_strategy. With
+    /// StrokeAware, words without a valid outline fall back to their
+    /// syllables.
+    pub fn split_syllables(&self, word: &str) -> Vec<String> {
+        match self.split_strategy {
+            SplitStrategy::Rough => syllable_split(word),
+            SplitStrategy::MaximalOnset => syllables::syllabify(word),
+            SplitStrategy::StrokeAware => match self.stroke_aware_split(word) {
+                Ok(strokes) => strokes.into_iter().map(|(s, _ch)| s).collect(),
+                Err(_e) => syllables::syllabify(word),
+            },
+        }
+    }
+
+    /// Splits word_root into one chunk per stroke, looking for stroke
+    /// boundaries in the whole root instead of within each syllable.
+    /// The fewest strokes win, syllables::syllabify() boundaries only
+    /// break ties: outlines whose strokes end inside a syllable less
+    /// often are preferred, then the ones with fewer keys.
+    pub fn stroke_aware_split(&self, word_root: &str) -> Result<Vec<(String, Chord)>, ErrBox> {
+        debug!("STROKE-AWARE ROOT: {}", word_root);
+
+        let chars: Vec<char> = word_root.chars().collect();
+
+        let mut syllable_ends = BTreeSet::new();
+        let mut pos = 0;
+        for syllable in syllables::syllabify(word_root) {
+            pos += syllable.chars().count();
+            syllable_ends.insert(pos);
+        }
+
+        let cost = |start: usize, strokes: &[(String, Chord)]| {
+            let mut pos = start;
+            let mut off_syllable = 0;
+            for (s, _ch) in strokes.iter() {
+                pos += s.chars().count();
+                if !syllable_ends.contains(&pos) {
+                    off_syllable += 1;
+                }
+            }
+
+            let (n_strokes, right_hand_only, keys, chords) = stroke_plan_cost(strokes);
+            (n_strokes, off_syllable, right_hand_only, keys, chords)
+        };
+
+        match self.best_stroke_plan(&chars, cost) {
+            Some(strokes) => {
+                for (s, ch) in strokes.iter() {
+                    debug!("REDUCE STROKE:\t{} ({})", s, ch.to_string());
+                }
+                Ok(strokes)
+            }
+            None => {
+                error!("NO-OUTLINE: {}", word_root);
+                Err(format!("no valid outline for {}", word_root).into())
+            }
+        }
+    }
+
+    /// Builds a word root out of its syllable chunks. Returns the root
+    /// chords and the chunks which were not known yet.
+    fn gen_root_chunk_chords(
+        &self,
+        word_root: &str,
+    ) -> Result<(Vec<ChordSeqItem>, Vec<ChordSequence>), ErrBox> {
+        let mut root_chords = Vec::new();
+        let mut new_chunks = Vec::new();
+
+        // Stroke-aware chunks come with their chords already
+        let chunks: Vec<(String, Option<Chord>)> = match self.split_strategy {
+            SplitStrategy::StrokeAware => self
+                .stroke_aware_split(word_root)?
+                .into_iter()
+                .map(|(s, ch)| (s, Some(ch)))
+                .collect(),
+            _other => self
+                .split_syllables(word_root)
+                .into_iter()
+                .map(|s| (s, None))
+                .collect(),
+        };
+
+        for (chunk, stroke) in chunks {
+            let mut chunk_chords = if let Some(chunk_chords) =
+                self.chunk_dict.get(&chunk.clone().into()).cloned()
+            {
+                debug!(
+                    "SKIP EXACT-CHUNK:\t{} ({})",
+                    chunk,
+                    chunk_chords.to_string()
                 );
-                existing.insert(chunk.get_word());
+                chunk_chords
             } else {
-                let mut new_set = BTreeSet::new();
-                new_set.insert(chunk.get_word());
-                self.chunk_conflict_dict.insert(chunk.clone(), new_set);
+                let chunk_chords = match stroke {
+                    Some(ch) => vec![ChordSeqItem::RootChord(chunk.clone(), ch)].into(),
+                    None => self.gen_chunk_chords(&chunk)?,
+                };
+                // This is an unknown chunk, add it to new chunks
+                new_chunks.push(chunk_chords.clone());
+                chunk_chords
+            };
+
+            root_chords.append(&mut chunk_chords.items);
+        }
+
+        Ok((root_chords, new_chunks))
+    }
+
+    /// Generate up to n alternative outlines for word, best first. Every
+    /// matching prefix and suffix is tried alongside the unsplit word,
+    /// and known word roots are offered next to freshly generated ones.
+    /// Compound words only get their compound outline.
+    pub fn gen_word_candidates(
+        &self,
+        word: &str,
+        n: usize,
+    ) -> Result<Vec<OutlineCandidate>, ErrBox> {
+        let word = sanitize_word(word)?;
+
+        if let Some((chords, new_chunks)) = self.gen_compound_chords(&word, true)? {
+            let score = OutlineScore {
+                strokes: chords.collapse().len(),
+                keys: chords.collapse().iter().map(|ch| ch.key_count()).sum(),
+                uses_affixes: chords.items.iter().any(|item| {
+                    matches!(item, ChordSeqItem::Prefix(_, _) | ChordSeqItem::Suffix(_, _))
+                }),
+                whole_root: false,
+            };
+
+            return Ok(vec![OutlineCandidate {
+                chords,
+                new_chunks,
+                score,
+            }]);
+        }
+
+        let analyses = self.analyse(&word)?;
+
+        // Different splits may still end up with the same strokes, only
+        // the best scoring one is kept
+        let mut candidates: BTreeMap<Vec<Chord>, OutlineCandidate> = BTreeMap::new();
+
+        for (prefixes, after_prefixes) in
+            strip_chains(&word, self.max_prefixes, true, |w| self.prefix_options(w))
+        {
+            for (suffixes, word_root) in strip_chains(&after_prefixes, self.max_suffixes, false, |w| {
+                self.suffix_options(w, &analyses)
+            }) {
+                let mut root_options = Vec::new();
+
+                if let Some(chords) = self.word_root_dict.get(&word_root.clone().into()) {
+                    root_options.push((chords.items.clone(), vec![], true));
+                }
+
+                match self.gen_root_chunk_chords(&word_root) {
+                    Ok((items, new_chunks)) => root_options.push((items, new_chunks, false)),
+                    Err(e) => debug!("SKIP CANDIDATE ROOT:\t{} ({})", word_root, e.to_string()),
+                }
+
+                for (root_items, new_chunks, whole_root) in root_options {
+                    let chords: ChordSequence = prefixes
+                        .iter()
+                        .map(|(s, ch)| ChordSeqItem::Prefix(s.clone(), *ch))
+                        .chain(root_items)
+                        .chain(
+                            suffixes
+                                .iter()
+                                .rev()
+                                .map(|(s, ch)| ChordSeqItem::Suffix(s.clone(), *ch)),
+                        )
+                        .collect::<Vec<_>>()
+                        .into();
+
+                    let score = OutlineScore {
+                        strokes: chords.collapse().len(),
+                        keys: chords.collapse().iter().map(|ch| ch.key_count()).sum(),
+                        uses_affixes: !prefixes.is_empty() || !suffixes.is_empty(),
+                        whole_root,
+                    };
+
+                    let is_better = match candidates.get(&chords.collapse()) {
+                        Some(existing) => score < existing.score,
+                        None => true,
+                    };
+
+                    if is_better {
+                        candidates.insert(
+                            chords.collapse(),
+                            OutlineCandidate {
+                                chords,
+                                new_chunks,
+                                score,
+                            },
+                        );
+                    }
+                }
+            }
+        }
+
+        if candidates.is_empty() {
+            return Err(format!("no candidate outlines for {}", word).into());
+        }
+
+        let mut candidates: Vec<OutlineCandidate> = candidates.into_values().collect();
+        candidates.sort_by(|a, b| a.score.cmp(&b.score).then_with(|| a.chords.cmp(&b.chords)));
+        candidates.truncate(n);
+
+        Ok(candidates)
+    }
+
+    /// The outline a writer would use for word with the generated
+    /// dictionaries: a shortcut if there is one, then an outline given
+    /// to add_word_with_outline(), the generated outline otherwise.
+    /// Word roots already in the dictionary keep their (possibly
+    /// conflict-resolved) outline.
+    pub fn word_outline(&self, word: &str) -> Result<ChordSequence, ErrBox> {
+        let word = sanitize_word(word)?;
+
+        if let Some(chord) = self.theory.shortcuts.get(&word) {
+            return Ok(ChordSequence::from_chord(word, chord.parse()?));
+        }
+
+        if let Some(chords) = self.word_outlines.get(&word) {
+            let root = root_only(chords);
+
+            // Swap in the root outline, conflict resolution may have
+            // changed it since
+            return Ok(match self.word_root_dict.get(&root.get_word().into()) {
+                Some(resolved) if *resolved != root => chords
+                    .items
+                    .iter()
+                    .filter(|item| !matches!(item, ChordSeqItem::Suffix(_, _)))
+                    .filter(|item| {
+                        !matches!(
+                            item,
+                            ChordSeqItem::RootChord(_, _) | ChordSeqItem::Disambiguation(_)
+                        )
+                    })
+                    .chain(resolved.items.iter())
+                    .chain(
+                        chords
+                            .items
+                            .iter()
+                            .filter(|item| matches!(item, ChordSeqItem::Suffix(_, _))),
+                    )
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .into(),
+                _other => chords.clone(),
+            });
+        }
+
+        let (chords, _new_chunks) = self.gen_word_chords(&word)?;
+
+        Ok(chords)
+    }
+
+    /// Check whether the root of an outline is already used by a
+    /// different word root
+    pub fn is_root_outline_taken(&self, chords: &ChordSequence) -> bool {
+        let root_chords = root_only(chords);
+
+        self.word_root_conflict_dict
+            .get(&root_chords)
+            .map(|words| words.iter().any(|w| *w != root_chords.get_word()))
+            .unwrap_or(false)
+    }
+
+    /// Every outline the word-root dictionary already uses, including
+    /// affixes, special characters and commands
+    pub fn used_outlines(&self) -> BTreeSet<Vec<Chord>> {
+        let mut ret: BTreeSet<Vec<Chord>> =
+            self.word_root_dict.values().map(|seq| seq.collapse()).collect();
+
+        ret.extend(self.prefixes_len_sorted.values().map(|ch| vec![*ch]));
+        ret.extend(self.suffixes_len_sorted.values().map(|ch| vec![*ch]));
+        ret.extend(self.infixes_len_sorted.values().map(|ch| vec![*ch]));
+
+        for ch_str in self
+            .theory
+            .special_chars
+            .values()
+            .chain(self.theory.commands.values())
+        {
+            if let Ok(ch) = ch_str.parse::<Chord>() {
+                ret.insert(vec![ch]);
+            }
+        }
+
+        ret
+    }
+
+    /// Move word_root from old_chords to new_chords, keeping the
+    /// conflict bookkeeping in sync
+    pub fn reassign_word_root(
+        &mut self,
+        word_root: &str,
+        old_chords: &ChordSequence,
+        new_chords: ChordSequence,
+    ) {
+        if let Some(words) = self.word_root_conflict_dict.get_mut(old_chords) {
+            words.remove(word_root);
+        }
+
+        self.word_root_conflict_dict
+            .entry(new_chords.clone())
+            .or_default()
+            .insert(word_root.to_string());
+
+        self.word_root_dict.insert(word_root.into(), new_chords);
+
+        if let Some(provenance) = self.root_provenance.get_mut(word_root) {
+            provenance.reassigned = true;
+        }
+    }
+
+    /// Returns Err on sanitization problems
+    pub fn gen_chunk_chords(&self, chunk: &str) -> Result<ChordSequence, ErrBox> {
+        match self.chunk_strategy {
+            ChunkStrategy::Greedy => self.gen_chunk_chords_greedy(chunk),
+            ChunkStrategy::Backtracking => self.gen_chunk_chords_backtracking(chunk),
+        }
+    }
+
+    /// Finds the outline with the fewest strokes for chunk. The greedy
+    /// outline wins ties, so outlines only change where the search
+    /// saves a stroke. Other ties are broken by the total number of keys
+    /// pressed, then by chord order.
+    pub fn gen_chunk_chords_backtracking(&self, chunk: &str) -> Result<ChordSequence, ErrBox> {
+        debug!("CHUNK: {}", chunk);
+
+        let chunk_chars: Vec<char> = chunk.chars().collect();
+
+        match self.best_stroke_plan(&chunk_chars, |_start, strokes| stroke_plan_cost(strokes)) {
+            Some(strokes) => {
+                if let Ok(greedy) = self.gen_chunk_chords_greedy(chunk) {
+                    if greedy.items.len() <= strokes.len() {
+                        trace!("KEEP GREEDY:\t{}", greedy.print_chords());
+                        return Ok(greedy);
+                    }
+                }
+
+                for (s, ch) in strokes.iter() {
+                    debug!("REDUCE STROKE:\t{} ({})", s, ch.to_string());
+                }
+                Ok(strokes
+                    .into_iter()
+                    .map(|(s, ch)| ChordSeqItem::RootChord(s, ch))
+                    .collect::<Vec<_>>()
+                    .into())
+            }
+            None => {
+                error!("NO-OUTLINE: {}", chunk);
+                Err(format!("no valid outline for {}", chunk).into())
             }
         }
-        Ok(word_chords)
     }
 
-    /// Generate
-    pub fn gen_word_chords(
+    /// Splits chars into strokes, keeping the plan with the lowest
+    /// cost. cost is given the position a plan starts at and its
+    /// strokes. None if chars can't be written at all.
+    fn best_stroke_plan<C: Ord>(
         &self,
-        word: &str,
-    ) -> Result<(ChordSequence, Vec<ChordSequence>), ErrBox> {
-        let word = word.trim().to_lowercase();
-
-        // Sanitize
-        if word.chars().any(|ch| {
-            !(ch.is_ascii_alphabetic() || dict_lookup::PL_DIACRITICS.contains(ch)) // Ascii alphabet + PL accents only
-		|| ch.is_whitespace() // No multi-word entries
-        }) {
-            return Err(format!("{:?} rejected - must be a single word made up exclusively of Polish and latin characters.", word).into());
-        }
-
-        debug!("WORD: {}", word);
+        chars: &[char],
+        cost: impl Fn(usize, &[(String, Chord)]) -> C,
+    ) -> Option<Vec<(String, Chord)>> {
+        // best_from[pos] holds the best known strokes for chars[pos..]
+        let mut best_from: Vec<Option<Vec<(String, Chord)>>> = vec![None; chars.len() + 1];
+        best_from[chars.len()] = Some(vec![]);
+
+        for pos in (0..chars.len()).rev() {
+            for (end, ch) in self.stroke_candidates(chars, pos) {
+                let Some(rest) = &best_from[end] else {
+                    continue;
+                };
 
-        let mut word_root = word.clone();
+                let mut candidate = vec![(chars[pos..end].iter().collect(), ch)];
+                candidate.extend(rest.iter().cloned());
 
-        let mut prefix: Option<ChordSeqItem> = None;
+                let is_better = match &best_from[pos] {
+                    Some(best) => cost(pos, &candidate) < cost(pos, best),
+                    None => true,
+                };
 
-        trace!("ATTEMPT PREFIX");
-        // Find all prefix matches
-        if !dict_lookup::PREFIX_EXCEPTIONS.contains(&word_root) {
-            if let Some((pref_str, pref_chord)) =
-                find_longest_affix(&word_root, &self.prefixes_len_sorted, 2, true)
-            {
-                debug!("REDUCE PREFIX:\t{}-", pref_str);
-                word_root = word_root.strip_prefix(&pref_str).unwrap().to_string();
-                prefix = Some(ChordSeqItem::Prefix(
-                    pref_str.clone().into(),
-                    pref_chord.clone(),
-                ));
+                if is_better {
+                    best_from[pos] = Some(candidate);
+                }
             }
-        } else {
-            trace!("SKIP PREFIX EXCEPTION");
         }
 
-        let mut suffix: Option<ChordSeqItem> = None;
+        best_from.swap_remove(0)
+    }
 
-        trace!("ATTEMPT SUFFIX");
-        // Find all suffix matches
-        if !dict_lookup::SUFFIX_EXCEPTIONS.contains(&word_root) {
-            if let Some((suff_str, suff_chord)) =
-                find_longest_affix(&word_root, &self.suffixes_len_sorted, 2, false)
-            {
-                debug!("REDUCE SUFFIX:\t-{}", suff_str,);
-                word_root = word_root.strip_suffix(&suff_str).unwrap().to_string();
-                suffix = Some(ChordSeqItem::Suffix(
-                    suff_str.clone().into(),
-                    suff_chord.clone(),
-                ));
+    /// Enumerates every single stroke that can begin at start, as
+    /// (end position, chord) pairs. Within a stroke, left-hand combos
+    /// come before center combos, which come before right-hand combos.
+    fn stroke_candidates(&self, chunk_chars: &[char], start: usize) -> BTreeSet<(usize, Chord)> {
+        let stages = [
+            &self.lh_combos_len_sorted,
+            &self.center_combos_len_sorted,
+            &self.rh_combos_len_sorted,
+        ];
+
+        let mut found = BTreeSet::new();
+        let mut visited = BTreeSet::new();
+        let mut todo = vec![(start, 0, Chord::default())];
+
+        while let Some((pos, stage, ch)) = todo.pop() {
+            if !visited.insert((pos, stage, ch)) {
+                continue;
             }
-        } else {
-            trace!("SKIP SUFFIX EXCEPTION");
-        }
 
-        let mut root_chords = Vec::new();
-        let mut new_chunks = Vec::new();
+            let remaining: String = chunk_chars[pos..].iter().collect();
 
-        if let Some(chords) = self.word_root_dict.get(&word_root.clone().into()).cloned() {
-            debug!("SKIP EXACT-ROOT:\t{} ({})", word_root, chords.to_string());
-            root_chords = chords.items;
-        } else {
-            for chunk in syllable_split(&word_root) {
-                let mut chunk_chords = if let Some(chunk_chords) =
-                    self.chunk_dict.get(&chunk.clone().into()).cloned()
-                {
-                    debug!(
-                        "SKIP EXACT-CHUNK:\t{} ({})",
-                        chunk,
-                        chunk_chords.to_string()
-                    );
-                    chunk_chords
-                } else {
-                    let chunk_chords = self.gen_chunk_chords(&chunk)?;
-                    // This is an unknown chunk, add it to new chunks
-                    new_chunks.push(chunk_chords.clone());
-                    chunk_chords
-                };
+            for (next_stage, combos) in stages.iter().enumerate().skip(stage) {
+                for (part_str, part_chord) in find_all_affixes(&remaining, combos, 1, true) {
+                    let mut merged = ch;
+                    if let Err(e) = merged.merge(&part_chord) {
+                        trace!("CONFLICT {}: {}", part_str, e.to_string());
+                        continue;
+                    }
 
-                root_chords.append(&mut chunk_chords.items);
+                    let next_pos = pos + part_str.chars().count();
+                    found.insert((next_pos, merged));
+                    todo.push((next_pos, next_stage, merged));
+                }
             }
         }
 
-        let chords: Vec<_> = prefix
-            .into_iter()
-            .chain(root_chords.into_iter())
-            .chain(suffix.into_iter())
-            .collect();
-
-        Ok((ChordSequence::new(chords), new_chunks))
+        found
     }
 
-    /// Returns Err on sanitization problems
-    pub fn gen_chunk_chords(&self, chunk: &str) -> Result<ChordSequence, ErrBox> {
+    /// Legacy single-pass reduction, kept for comparison
+    pub fn gen_chunk_chords_greedy(&self, chunk: &str) -> Result<ChordSequence, ErrBox> {
         debug!("CHUNK: {}", chunk);
 
         let mut remaining_chunk_chars = chunk.to_owned();
@@ -345,71 +1172,444 @@ impl Generator {
         Ok(chunk_chords)
     }
 
-    pub fn save_syllables(&self, f: File) -> Result<(), ErrBox> {
-        let chunk_iter = self
-            .chunk_dict
-            .iter()
-            .map(|(s, ch_seq)| (ch_seq.print_chords(), format!("{{&{}}}", s)));
+    /// Write word roots and syllables as one RTF/CRE dictionary, word
+    /// roots taking precedence. Entries without an RTF/CRE equivalent
+    /// are only reported.
+    pub fn save_rtf(&self, f: File) -> Result<RtfReport, ErrBox> {
+        let mut entries = self.syllables_dict();
+        entries.extend(self.word_roots_dict());
 
-        let prefix_iter = self
-            .prefixes_len_sorted
-            .iter()
-            .map(|(s, ch)| (ch.to_string(), format!("{}{}", s, "{^}")));
+        rtf::write_rtf(f, &entries)
+    }
+
+    pub fn save_syllables(&self, format: &dyn DictFormat, mut w: impl Write) -> Result<(), ErrBox> {
+        format.write_entries(&mut w, &self.syllables_entries())
+    }
+
+    /// Outline to translation map saved as syllables.json
+    pub fn syllables_dict(&self) -> BTreeMap<String, String> {
+        output::entries_to_map(&self.syllables_entries())
+    }
+
+    /// Syllable chunks and the theory's own strokes
+    pub fn syllables_entries(&self) -> Vec<DictEntry> {
+        let chunk_iter = self.chunk_dict.iter().map(|(s, ch_seq)| {
+            DictEntry::new(
+                ch_seq.print_chords(),
+                format!("{{&{}}}", s),
+                EntrySource::Chunk,
+            )
+        });
+
+        self.with_theory_entries(chunk_iter)
+    }
 
-        let suffix_iter = self
-            .suffixes_len_sorted
+    /// Annotates word_roots.json with how common each word root is
+    pub fn save_word_root_frequencies(&self, f: File) -> Result<(), ErrBox> {
+        let annotations: BTreeMap<String, (String, u64)> = self
+            .word_root_dict
             .iter()
-            .map(|(s, ch)| (ch.to_string(), format!("{}{}", "{^}", s)));
+            .map(|(s, ch_seq)| {
+                (
+                    ch_seq.print_chords(),
+                    (s.0.clone(), self.root_frequency(&s.0)),
+                )
+            })
+            .collect();
 
-        let special_char_iter = dict_lookup::SPECIAL_CHARS
-            .into_iter()
-            .map(|(s, ch)| (ch.to_string(), s.to_string()));
+        serde_json::to_writer_pretty(f, &annotations)?;
 
-        let commands_iter = dict_lookup::COMMANDS
-            .into_iter()
-            .map(|(s, ch)| (ch.to_string(), s.to_string()));
+        Ok(())
+    }
+
+    pub fn save_word_roots(
+        &self,
+        format: &dyn DictFormat,
+        mut w: impl Write,
+    ) -> Result<(), ErrBox> {
+        // Only one word per outline makes it into the dictionary
+        for (outline, words) in self.word_root_conflict_dict.iter() {
+            if words.len() > 1 {
+                warn!(
+                    "SAVE CONFLICT:\t{} keeps only one of {:?}",
+                    outline.print_chords(),
+                    words
+                );
+            }
+        }
+
+        format.write_entries(&mut w, &self.word_roots_entries())
+    }
 
-        let chained = chunk_iter.chain(prefix_iter).chain(suffix_iter).chain(special_char_iter).chain(commands_iter);
+    /// Outline to translation map saved as word_roots.json
+    pub fn word_roots_dict(&self) -> BTreeMap<String, String> {
+        output::entries_to_map(&self.word_roots_entries())
+    }
 
-        let final_dict: BTreeMap<String, String> = chained.collect();
+    /// Word roots and the theory's own strokes
+    pub fn word_roots_entries(&self) -> Vec<DictEntry> {
+        let word_root_iter = self.word_root_dict.iter().map(|(s, ch_seq)| {
+            DictEntry::new(
+                ch_seq.print_chords(),
+                LenSortableString::into(s.clone()),
+                EntrySource::WordRoot,
+            )
+        });
+
+        self.with_theory_entries(word_root_iter)
+    }
 
-        serde_json::to_writer_pretty(f, &final_dict)?;
+    /// Append affixes, special characters and commands to entries. Later
+    /// entries win on the same outline.
+    fn with_theory_entries(&self, entries: impl Iterator<Item = DictEntry>) -> Vec<DictEntry> {
+        // Theory entries come with the table entry they rely on
+        let theory_entry = |section, text: &str, outline, translation, source| {
+            let mut entry = DictEntry::new(outline, translation, source);
+            entry
+                .provenance
+                .combos
+                .push(self.theory_combo(section, text));
+            entry
+        };
 
-        Ok(())
+        let prefix_iter = self.prefixes_len_sorted.iter().map(|(s, ch)| {
+            let translation = format!("{}{{^}}", s.0);
+            theory_entry(
+                "prefixes",
+                &s.0,
+                ch.to_string(),
+                translation,
+                EntrySource::Prefix,
+            )
+        });
+
+        let suffix_iter = self.suffixes_len_sorted.iter().map(|(s, ch)| {
+            let translation = format!("{{^}}{}", s.0);
+            theory_entry(
+                "suffixes",
+                &s.0,
+                ch.to_string(),
+                translation,
+                EntrySource::Suffix,
+            )
+        });
+
+        let infix_iter = self.infixes_len_sorted.iter().map(|(s, ch)| {
+            let translation = format!("{{^{}^}}", s.0);
+            theory_entry(
+                "infixes",
+                &s.0,
+                ch.to_string(),
+                translation,
+                EntrySource::Infix,
+            )
+        });
+
+        let special_char_iter = self.theory.special_chars.iter().map(|(s, ch)| {
+            let source = EntrySource::SpecialChar;
+            theory_entry("special_chars", s, ch.to_string(), s.to_string(), source)
+        });
+
+        let commands_iter = self.theory.commands.iter().map(|(s, ch)| {
+            theory_entry(
+                "commands",
+                s,
+                ch.to_string(),
+                s.to_string(),
+                EntrySource::Command,
+            )
+        });
+
+        let chained = entries
+            .chain(prefix_iter)
+            .chain(suffix_iter)
+            .chain(infix_iter)
+            .chain(special_char_iter)
+            .chain(commands_iter);
+
+        output::dedup_entries(chained)
     }
 
-    pub fn save_word_roots(&self, f: File) -> Result<(), ErrBox> {
-        let word_root_iter = self
+    /// word_roots_entries() and syllables_entries() with the matched
+    /// combos, syllable split and conflicts of every word root and chunk
+    pub fn provenance_report(&self) -> ProvenanceReport {
+        let word_roots: BTreeMap<String, (&String, &ChordSequence)> = self
             .word_root_dict
             .iter()
-            .map(|(s, ch_seq)| (ch_seq.print_chords(), LenSortableString::into(s.clone())));
+            .map(|(s, ch_seq)| (ch_seq.print_chords(), (&s.0, ch_seq)))
+            .collect();
 
-        let prefix_iter = self
-            .prefixes_len_sorted
+        let chunks: BTreeMap<String, (&String, &ChordSequence)> = self
+            .chunk_dict
             .iter()
-            .map(|(s, ch)| (ch.to_string(), format!("{}{}", s, "{^}")));
+            .map(|(s, ch_seq)| (ch_seq.print_chords(), (&s.0, ch_seq)))
+            .collect();
 
-        let suffix_iter = self
-            .suffixes_len_sorted
-            .iter()
-            .map(|(s, ch)| (ch.to_string(), format!("{}{}", "{^}", s)));
+        let mut word_root_entries = self.word_roots_entries();
+        for entry in word_root_entries.iter_mut() {
+            let Some((word_root, ch_seq)) = word_roots.get(&entry.outline) else {
+                continue;
+            };
+
+            let provenance = &mut entry.provenance;
+            provenance.root = self.root_provenance.get(*word_root).cloned();
+
+            match self.theory.shortcuts.get(*word_root) {
+                Some(chord) => provenance
+                    .combos
+                    .push(ComboRef::new("shortcuts", word_root, chord)),
+                None => {
+                    provenance.combos = self.outline_combos(ch_seq);
+                    provenance.syllables = self.split_syllables(word_root);
+                }
+            }
+
+            provenance.conflicts_with =
+                conflicting_words(&self.word_root_conflict_dict, ch_seq, word_root);
+        }
+
+        let mut syllable_entries = self.syllables_entries();
+        for entry in syllable_entries.iter_mut() {
+            let Some((chunk, ch_seq)) = chunks.get(&entry.outline) else {
+                continue;
+            };
+
+            entry.provenance.combos = self.outline_combos(ch_seq);
+            entry.provenance.conflicts_with =
+                conflicting_words(&self.chunk_conflict_dict, ch_seq, chunk);
+        }
 
-        let special_char_iter = dict_lookup::SPECIAL_CHARS
+        ProvenanceReport {
+            dictionaries: [
+                ("word_roots".to_string(), word_root_entries),
+                ("syllables".to_string(), syllable_entries),
+            ]
             .into_iter()
-            .map(|(s, ch)| (ch.to_string(), s.to_string()));
+            .collect(),
+        }
+    }
+
+    /// The theory combos behind every stroke of a word root or chunk.
+    /// Strokes no combos add up to, e.g. ones changed by conflict
+    /// resolution, are left out.
+    pub fn outline_combos(&self, ch_seq: &ChordSequence) -> Vec<ComboRef> {
+        let mut ret = Vec::new();
+
+        for item in ch_seq.items.iter() {
+            let ChordSeqItem::RootChord(text, ch) = item else {
+                continue;
+            };
+
+            match self.stroke_combos(text, ch) {
+                Some(combos) => ret.extend(
+                    combos
+                        .into_iter()
+                        .map(|(section, text)| self.theory_combo(section, &text)),
+                ),
+                None => trace!("NO COMBOS:\t{} ({})", text, ch.to_string()),
+            }
+        }
+
+        ret
+    }
+
+    /// Left-hand, center and right-hand combos, in that order, which
+    /// spell text and add up to exactly ch, as (section, combo text)
+    /// pairs. Longer combos are tried first.
+    fn stroke_combos(&self, text: &str, ch: &Chord) -> Option<Vec<(&'static str, String)>> {
+        let stages = [
+            ("left_hand_combos", &self.lh_combos_len_sorted),
+            ("center_combos", &self.center_combos_len_sorted),
+            ("right_hand_combos", &self.rh_combos_len_sorted),
+        ];
+
+        let chars: Vec<char> = text.chars().collect();
+
+        let mut todo = vec![(0, 0, Chord::default(), vec![])];
+
+        while let Some((pos, stage, merged, path)) = todo.pop() {
+            if pos == chars.len() {
+                if merged == *ch {
+                    return Some(path);
+                }
+                continue;
+            }
+
+            let remaining: String = chars[pos..].iter().collect();
+
+            let mut next = Vec::new();
+            for (next_stage, (section, combos)) in stages.iter().enumerate().skip(stage) {
+                for (part_str, part_chord) in find_all_affixes(&remaining, combos, 1, true) {
+                    let mut next_merged = merged;
+                    if next_merged.merge(&part_chord).is_err() || !ch.contains(&next_merged) {
+                        continue;
+                    }
 
-        let commands_iter = dict_lookup::COMMANDS
+                    let mut next_path = path.clone();
+                    let next_pos = pos + part_str.chars().count();
+                    next_path.push((*section, part_str));
+                    next.push((next_pos, next_stage, next_merged, next_path));
+                }
+            }
+
+            // Popped in reverse, so that the first match is tried first
+            todo.extend(next.into_iter().rev());
+        }
+
+        None
+    }
+
+    /// A theory table entry as used by this generator
+    fn theory_combo(&self, section: &'static str, text: &str) -> ComboRef {
+        let chord = self
+            .theory
+            .tables()
             .into_iter()
-            .map(|(s, ch)| (ch.to_string(), s.to_string()));
+            .find(|(name, _table)| *name == section)
+            .and_then(|(_name, table)| table.get(text))
+            .cloned()
+            .unwrap_or_default();
 
-        let chained = word_root_iter.chain(prefix_iter).chain(suffix_iter).chain(special_char_iter).chain(commands_iter);
+        ComboRef::new(section, text, &chord)
+    }
+}
+
+/// Parse a theory table into chords keyed by descending text length.
+/// chord_prefix is prepended to every chord string before parsing.
+fn parse_table(
+    table: &BTreeMap<String, String>,
+    chord_prefix: &str,
+) -> Result<BTreeMap<LenSortableString<false>, Chord>, ErrBox> {
+    table
+        .iter()
+        .map(|(txt, chord)| {
+            format!("{}{}", chord_prefix, chord)
+                .parse()
+                .map(|ch| (txt.as_str().into(), ch))
+        })
+        .collect()
+}
 
-        let final_dict: BTreeMap<String, String> = chained.collect();
+/// The parts of a compound outline, without the infixes joining them.
+/// Other outlines are a single part.
+pub fn compound_parts(chords: &ChordSequence) -> Vec<ChordSequence> {
+    chords
+        .items
+        .split(|item| matches!(item, ChordSeqItem::Infix(_, _)))
+        .map(|part| part.to_vec().into())
+        .collect()
+}
 
-        serde_json::to_writer_pretty(f, &final_dict)?;
+/// Words other than word sharing the outline ch_seq in a conflict dict
+fn conflicting_words(
+    conflict_dict: &BTreeMap<ChordSequence, BTreeSet<String>>,
+    ch_seq: &ChordSequence,
+    word: &str,
+) -> Vec<String> {
+    conflict_dict
+        .get(ch_seq)
+        .map(|words| words.iter().filter(|w| *w != word).cloned().collect())
+        .unwrap_or_default()
+}
 
-        Ok(())
+fn join_compound(
+    left: ChordSequence,
+    infix: &str,
+    infix_chord: Chord,
+    right: ChordSequence,
+) -> ChordSequence {
+    left.items
+        .into_iter()
+        .chain(std::iter::once(ChordSeqItem::Infix(infix.to_string(), infix_chord)))
+        .chain(right.items)
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// Strips the affixes off a sequence, leaving only the root chords and
+/// disambiguation strokes
+pub fn root_only(chords: &ChordSequence) -> ChordSequence {
+    chords
+        .items
+        .iter()
+        .filter(|item| {
+            matches!(
+                item,
+                ChordSeqItem::RootChord(_, _) | ChordSeqItem::Disambiguation(_)
+            )
+        })
+        .cloned()
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// Lowercases word and rejects anything that isn't a single Polish/latin word
+pub fn sanitize_word(word: &str) -> Result<String, ErrBox> {
+    let word = word.trim().to_lowercase();
+
+    if word.chars().any(|ch| {
+        !(ch.is_ascii_alphabetic() || dict_lookup::PL_DIACRITICS.contains(ch) || ch == '-') // Ascii alphabet + PL accents only
+            || ch.is_whitespace() // No multi-word entries
+    }) || word.starts_with('-') || word.ends_with('-') || word.contains("--") // Hyphens only join compound parts
+    {
+        return Err(format!("{:?} rejected - must be a single word made up exclusively of Polish and latin characters.", word).into());
+    }
+
+    Ok(word)
+}
+
+/// Every way of stripping up to depth affixes off word, the unstripped
+/// word included, with what's left of it. options gives the affixes
+/// which may be stripped next. Affixes are listed outermost first.
+fn strip_chains(
+    word: &str,
+    depth: usize,
+    is_prefix: bool,
+    options: impl Fn(&str) -> Vec<(String, Chord)>,
+) -> Vec<(Vec<(String, Chord)>, String)> {
+    let mut ret = vec![(vec![], word.to_owned())];
+    let mut last_level = ret.clone();
+
+    for _level in 0..depth {
+        let mut next_level = Vec::new();
+
+        for (chain, rest) in last_level {
+            for (affix, chord) in options(&rest) {
+                let stripped = if is_prefix {
+                    rest.strip_prefix(affix.as_str())
+                } else {
+                    rest.strip_suffix(affix.as_str())
+                };
+
+                let mut chain = chain.clone();
+                chain.push((affix.clone(), chord));
+                next_level.push((chain, stripped.unwrap().to_owned()));
+            }
+        }
+
+        ret.extend(next_level.iter().cloned());
+        last_level = next_level;
     }
+
+    ret
+}
+
+/// Orders candidate strokes: fewest strokes first, then fewest strokes
+/// pressing only right-hand keys (a syllable starts on the left hand or
+/// the vowels, not on -BSG for "ż"), then fewest keys, then plain chord
+/// order to keep the choice deterministic.
+fn stroke_plan_cost(strokes: &[(String, Chord)]) -> (usize, usize, u32, Vec<Chord>) {
+    let right_hand_only = strokes
+        .iter()
+        .filter(|(_s, ch)| ch.keys().all(|k| k.side == Side::Right))
+        .count();
+
+    (
+        strokes.len(),
+        right_hand_only,
+        strokes.iter().map(|(_s, ch)| ch.key_count()).sum(),
+        strokes.iter().map(|(_s, ch)| *ch).collect(),
+    )
 }
 
 pub fn find_longest_affix<const ASC: bool, T: Clone>(
@@ -449,6 +1649,34 @@ pub fn find_longest_affix<const ASC: bool, T: Clone>(
     None
 }
 
+/// Like find_longest_affix, but returns every match, longest first
+pub fn find_all_affixes<const ASC: bool, T: Clone>(
+    needle: &str,
+    haystack: &BTreeMap<LenSortableString<ASC>, T>,
+    min_match_len: usize,
+    is_prefix: bool,
+) -> Vec<(String, T)> {
+    let ndl_vec = needle.chars().collect::<Vec<_>>();
+    let needle_len = ndl_vec.len();
+
+    let mut ret = Vec::new();
+
+    for n_chars in (min_match_len.max(1)..=needle_len).rev() {
+        let slice_string: String = if is_prefix {
+            ndl_vec[..n_chars].iter().collect()
+        } else {
+            ndl_vec[(needle_len - n_chars)..].iter().collect()
+        };
+
+        if let Some(item) = haystack.get(&slice_string.clone().into()) {
+            trace!("HIT {}", slice_string);
+            ret.push((slice_string, item.clone()));
+        }
+    }
+
+    ret
+}
+
 const CONSONANT_SUBSTR: &'static str =
     "(ch|cz|dz|dź|dż|sz|rz|b|c|ć|d|f|g|h|j|k|l|ł|m|n|ń|p|q|r|s|ś|t|v|w|x|z|ź|ż)";
 const VOWEL_SUBSTR: &'static str = "(ia|ią|ie|ię|io|iu|ió|au|eu|a|ą|e|ę|i|o|ó|u|y)";
@@ -569,4 +1797,291 @@ mod test {
 
         assert_eq!(syllable_split("marznąć"), vec!["marz", "nąć"].to_owned());
     }
+
+    #[test]
+    fn test_backtracking_finds_fewer_strokes() -> Result<(), ErrBox> {
+        let mut gen = Generator::new(Theory::builtin())?;
+
+        gen.chunk_strategy = ChunkStrategy::Greedy;
+        assert_eq!(gen.gen_chunk_chords("ciół")?.print_chords(), "TI/EIULB");
+        assert_eq!(gen.gen_chunk_chords("łkiem")?.print_chords(), "KLJE*I/KP-");
+
+        gen.chunk_strategy = ChunkStrategy::Backtracking;
+        assert_eq!(gen.gen_chunk_chords("ciół")?.print_chords(), "ZSJULB");
+        assert_eq!(gen.gen_chunk_chords("łkiem")?.print_chords(), "KLJEICS");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_backtracking_golden() -> Result<(), ErrBox> {
+        let gen = Generator::new(Theory::builtin())?;
+
+        // Syllables start on the left hand or the vowels where they can
+        let cases = [
+            ("żółw", "TPJ/EIULBW"),
+            ("kakaowy", "KA/KA/AU/VIAU"),
+            ("boisko", "PJAU/IS/XFK-O"),
+            // No left-hand x
+            ("xero", "-LS/E/RAU"),
+        ];
+
+        for (word, expected) in cases {
+            let (chords, _new_chunks) = gen.gen_word_chords(word)?;
+            assert_eq!(chords.print_chords(), expected, "{}", word);
+        }
+
+        // Not even the search on its own starts a syllable on -BSG
+        let chars: Vec<char> = "żółw".chars().collect();
+        let plan = gen
+            .best_stroke_plan(&chars, |_start, strokes| stroke_plan_cost(strokes))
+            .unwrap();
+        let plan: Vec<String> = plan.iter().map(|(_s, ch)| ch.to_string()).collect();
+        assert_eq!(plan, vec!["TPJ", "EIULBW"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stroke_aware_split() -> Result<(), ErrBox> {
+        let mut gen = Generator::new(Theory::builtin())?;
+
+        // Two syllables, one stroke
+        let strokes = gen.stroke_aware_split("krzesło")?;
+        assert_eq!(strokes.len(), 1);
+        assert_eq!(strokes[0].0, "krzesło");
+
+        let (rough, _new_chunks) = gen.gen_word_chords("dziewczyna")?;
+        assert_eq!(rough.print_chords(), "TVJEI/PVIAU/XFLRA");
+
+        gen.split_strategy = SplitStrategy::StrokeAware;
+        let (stroke_aware, _new_chunks) = gen.gen_word_chords("dziewczyna")?;
+        assert_eq!(stroke_aware.print_chords(), "TVJEIGWY/XFLRA");
+        assert_eq!(stroke_aware.get_word(), "dziewczyna");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stacked_affixes() -> Result<(), ErrBox> {
+        let mut gen = Generator::new(Theory::builtin())?;
+        gen.max_prefixes = 2;
+        gen.max_suffixes = 2;
+
+        let affixes = |chords: &ChordSequence| -> Vec<String> {
+            chords
+                .items
+                .iter()
+                .filter_map(|item| match item {
+                    ChordSeqItem::Prefix(s, _) => Some(format!("{}-", s)),
+                    ChordSeqItem::Suffix(s, _) => Some(format!("-{}", s)),
+                    _other => None,
+                })
+                .collect()
+        };
+
+        let (chords, _new_chunks) = gen.gen_word_chords("niedopowiedzenie")?;
+        assert_eq!(affixes(&chords), vec!["nie-", "do-", "-nie"]);
+        assert_eq!(chords.get_word(), "niedopowiedzenie");
+
+        // Innermost suffix first
+        let (chords, _new_chunks) = gen.gen_word_chords("marzanna")?;
+        assert_eq!(affixes(&chords), vec!["-an", "-na"]);
+        assert_eq!(chords.get_word(), "marzanna");
+
+        // do- would leave a single letter
+        let (chords, _new_chunks) = gen.gen_word_chords("dom")?;
+        assert!(affixes(&chords).is_empty());
+
+        let candidates = gen.gen_word_candidates("niedopowiedzenie", 100)?;
+        assert!(candidates
+            .iter()
+            .any(|c| affixes(&c.chords) == vec!["nie-", "do-"]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_compound_words() -> Result<(), ErrBox> {
+        use crate::plover_engine::{format_translations, StenoDictionary};
+
+        let mut gen = Generator::new(Theory::builtin())?;
+
+        let infixes = |chords: &ChordSequence| -> Vec<String> {
+            chords
+                .items
+                .iter()
+                .filter_map(|item| match item {
+                    ChordSeqItem::Infix(s, _) => Some(s.clone()),
+                    _other => None,
+                })
+                .collect()
+        };
+
+        let chords = gen.add_word_root("biało-czerwony")?;
+        assert_eq!(infixes(&chords), vec!["o-"]);
+        assert_eq!(chords.get_word(), "biało-czerwony");
+
+        // Both parts are roots of their own
+        assert!(gen.word_root_dict.contains_key(&"biał".to_string().into()));
+        assert!(!gen.word_root_dict.contains_key(&"białczerw".to_string().into()));
+
+        let dict = StenoDictionary::from_generator(&gen)?;
+        assert_eq!(
+            format_translations(&dict.translate(&chords.collapse())),
+            "biało-czerwony"
+        );
+
+        // Lookup and conflict resolution see the same outline
+        let candidates = gen.gen_word_candidates("biało-czerwony", 10)?;
+        assert_eq!(candidates.len(), 1);
+        assert!(candidates[0].chords == chords);
+
+        gen.add_word_root("woda")?;
+        gen.add_word_root("ciąg")?;
+
+        let (chords, _new_chunks) = gen.gen_word_chords("wodociąg")?;
+        assert!(infixes(&chords).is_empty());
+
+        gen.split_compounds = true;
+        let (chords, new_chunks) = gen.gen_word_chords("wodociąg")?;
+        assert_eq!(infixes(&chords), vec!["o"]);
+        assert_eq!(chords.get_word(), "wodociąg");
+
+        // "wod" isn't a root yet, its chunks come with the compound
+        let new_chunks: Vec<String> = new_chunks.iter().map(|c| c.get_word()).collect();
+        assert_eq!(new_chunks, vec!["wod"]);
+
+        gen.add_word_root("wodociąg")?;
+        assert!(gen.word_root_dict.contains_key(&"wod".to_string().into()));
+        assert!(gen.chunk_dict.contains_key(&"wod".to_string().into()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_word_candidates_are_ranked() -> Result<(), ErrBox> {
+        let gen = Generator::new(Theory::builtin())?;
+
+        let candidates = gen.gen_word_candidates("przedszkole", 10)?;
+
+        assert!(candidates.len() > 1);
+        assert!(candidates.len() <= 10);
+        assert!(candidates.windows(2).all(|w| w[0].score <= w[1].score));
+
+        // One candidate per outline
+        let outlines: BTreeSet<Vec<Chord>> =
+            candidates.iter().map(|c| c.chords.collapse()).collect();
+        assert_eq!(outlines.len(), candidates.len());
+
+        // Every candidate still spells the same word
+        for candidate in candidates.iter() {
+            assert_eq!(candidate.chords.get_word(), "przedszkole");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_provenance_report() -> Result<(), ErrBox> {
+        let mut gen = Generator::new(Theory::builtin())?;
+
+        gen.add_word_root("przedszkolem")?;
+        gen.add_word_root("przedszkolami")?;
+        gen.add_word_root("kot")?;
+
+        let report = gen.provenance_report();
+        let word_roots = &report.dictionaries["word_roots"];
+
+        let szkol = word_roots
+            .iter()
+            .find(|e| e.translation == "szkol")
+            .ok_or("szkol missing")?;
+        let root = szkol.provenance.root.as_ref().ok_or("no root provenance")?;
+
+        assert_eq!(root.source, RootSource::AffixStripping);
+        assert_eq!(root.word, "przedszkolem");
+        assert_eq!(root.affixes, vec!["przed-", "-em"]);
+        assert_eq!(root.exact_root_hits, 1);
+
+        // Every combo is a known theory entry with its page
+        assert!(!szkol.provenance.combos.is_empty());
+        assert!(szkol.provenance.combos.iter().all(|c| c.page.is_some()));
+
+        let kot = word_roots
+            .iter()
+            .find(|e| e.translation == "kot")
+            .ok_or("kot missing")?;
+        assert_eq!(
+            kot.provenance.root.as_ref().map(|r| r.source),
+            Some(RootSource::Chunks)
+        );
+        assert_eq!(kot.provenance.syllables, vec!["kot"]);
+
+        let prefix = word_roots
+            .iter()
+            .find(|e| e.translation == "przed{^}")
+            .ok_or("przed- missing")?;
+        assert_eq!(prefix.provenance.source, EntrySource::Prefix);
+        assert_eq!(prefix.provenance.combos[0].section, "prefixes");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_suffix_constraints() -> Result<(), ErrBox> {
+        let mut theory = Theory::builtin();
+        theory.suffix_exceptions.clear();
+
+        let mut gen = Generator::new(theory)?;
+
+        let last_suffix = |gen: &Generator, word: &str| -> Result<Option<String>, ErrBox> {
+            Ok(match gen.gen_word_chords(word)?.0.items.last() {
+                Some(ChordSeqItem::Suffix(s, _)) => Some(s.clone()),
+                _other => None,
+            })
+        };
+
+        // Without analyses every suffix is fair game
+        assert_eq!(last_suffix(&gen, "kielich")?.as_deref(), Some("ich"));
+
+        let mut morph = crate::morphology::TableMorphology::default();
+        morph.insert("kielich", "kielich", "subst:sg:nom:m3");
+        morph.insert("biernych", "bierny", "adj:pl:gen:m1.m2.m3.f.n:pos");
+        morph.insert("mały", "mały", "adj:sg:nom:m1.m2.m3:pos");
+        gen.morphology = Some(Box::new(morph));
+
+        assert_eq!(last_suffix(&gen, "kielich")?, None);
+        assert_eq!(last_suffix(&gen, "biernych")?.as_deref(), Some("nych"));
+        // "-ały" is for verbs
+        assert_eq!(last_suffix(&gen, "mały")?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_min_root_len_at_default_depth() -> Result<(), ErrBox> {
+        let gen = Generator::new(Theory::builtin())?;
+        assert_eq!((gen.max_prefixes, gen.max_suffixes), (1, 1));
+
+        let affixes = |word: &str| -> Result<Vec<String>, ErrBox> {
+            Ok(gen
+                .gen_word_chords(word)?
+                .0
+                .items
+                .iter()
+                .filter_map(|item| match item {
+                    ChordSeqItem::Prefix(s, _) => Some(format!("{}-", s)),
+                    ChordSeqItem::Suffix(s, _) => Some(format!("-{}", s)),
+                    _other => None,
+                })
+                .collect())
+        };
+
+        // prze- leaves "pych", -ych would leave a one-letter root
+        assert_eq!(affixes("przepych")?, vec!["prze-"]);
+        assert_eq!(root_only(&gen.gen_word_chords("przepych")?.0).get_word(), "pych");
+
+        Ok(())
+    }
 }
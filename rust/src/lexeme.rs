@@ -0,0 +1,411 @@
+//! Lexeme-aware generation. Forms of one lexeme share a stem, which
+//! gets a single root outline; the endings are written with SUFFIXES
+//! strokes. Without this, every form is split on its own and forms of
+//! one word can end up with unrelated roots.
+//!
+//! Lexemes come either from the lines of an odm word list or from
+//! lemmas given by a Morphology backend.
+
+use log::{debug, warn};
+use serde::Serialize;
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+};
+
+use crate::{
+    chord::{ChordSeqItem, ChordSequence},
+    generator::Generator,
+    morphology::Morphology,
+    ErrBox,
+};
+
+/// Lemma => forms
+pub type Lexemes = BTreeMap<String, BTreeSet<String>>;
+
+/// A form whose stem/ending boundary differs from the suffix the
+/// generator would strip on its own with find_longest_affix()
+#[derive(Clone, Debug, Serialize)]
+pub struct BoundaryDisagreement {
+    pub lemma: String,
+    pub form: String,
+    pub stem: String,
+    pub ending: String,
+    /// None if no suffix would be stripped
+    pub affix_suffix: Option<String>,
+}
+
+/// A form which couldn't be expressed as stem + suffix stroke and got
+/// its own outline instead
+#[derive(Clone, Debug, Serialize)]
+pub struct LexemeFailure {
+    pub lemma: String,
+    pub form: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct LexemeReport {
+    pub lexemes: usize,
+    /// Forms sharing their lexeme's root outline
+    pub consistent_forms: usize,
+    pub disagreements: Vec<BoundaryDisagreement>,
+    pub failures: Vec<LexemeFailure>,
+    /// Lemmas of lexemes with at least one failed form
+    pub inconsistent_lexemes: Vec<String>,
+}
+
+impl LexemeReport {
+    pub fn save(&self, f: File) -> Result<(), ErrBox> {
+        serde_json::to_writer_pretty(f, self)?;
+        Ok(())
+    }
+}
+
+/// The stem writing the most forms as stem + nothing or one SUFFIXES
+/// stroke, and how many forms that is. Longer stems win ties. This is
+/// not always the longest common prefix: with alternations like
+/// kot/kocie it's too short to be of use, and "czyt" covers czytam and
+/// czytasz where "czyta" would need -m and -sz.
+pub fn choose_stem(gen: &Generator, forms: &BTreeSet<String>) -> (String, usize) {
+    let mut candidates: BTreeSet<&str> = BTreeSet::new();
+
+    for form in forms {
+        for (idx, _c) in form.char_indices().skip(2) {
+            candidates.insert(&form[..idx]);
+        }
+        candidates.insert(form);
+    }
+
+    candidates
+        .into_iter()
+        .map(|stem| {
+            let covered = forms
+                .iter()
+                .filter(|form| {
+                    form.strip_prefix(stem).is_some_and(|ending| {
+                        ending.is_empty() || gen.suffix_chord(ending).is_some()
+                    })
+                })
+                .count();
+            (covered, stem.chars().count(), stem)
+        })
+        .max()
+        .map(|(covered, _len, stem)| (stem.to_string(), covered))
+        .unwrap_or_default()
+}
+
+/// Add all forms of a lexeme to gen. Forms which can't be written as
+/// the stem plus a SUFFIXES stroke, or all forms if no stem is shared
+/// by at least two of them, are added with add_word_root() and
+/// reported.
+pub fn add_lexeme(
+    gen: &mut Generator,
+    lemma: &str,
+    forms: &BTreeSet<String>,
+    report: &mut LexemeReport,
+) -> Result<(), ErrBox> {
+    report.lexemes += 1;
+
+    let failures_before = report.failures.len();
+    add_forms(gen, lemma, forms, report)?;
+
+    if report.failures.len() > failures_before {
+        report.inconsistent_lexemes.push(lemma.to_string());
+    }
+
+    Ok(())
+}
+
+fn add_forms(
+    gen: &mut Generator,
+    lemma: &str,
+    forms: &BTreeSet<String>,
+    report: &mut LexemeReport,
+) -> Result<(), ErrBox> {
+    let fail = |form: &String, reason: String| LexemeFailure {
+        lemma: lemma.to_string(),
+        form: form.clone(),
+        reason,
+    };
+
+    // Nothing to share, the word is split like any other
+    if forms.len() == 1 {
+        for form in forms {
+            if add_alone(gen, lemma, form, report) {
+                report.consistent_forms += 1;
+            }
+        }
+        return Ok(());
+    }
+
+    let (stem, covered) = choose_stem(gen, forms);
+
+    if covered < 2 {
+        for form in forms {
+            report
+                .failures
+                .push(fail(form, "no stem shared by two forms".to_string()));
+            add_alone(gen, lemma, form, report);
+        }
+        return Ok(());
+    }
+
+    let (stem_chords, mut new_chunks) = match gen.gen_stem_chords(&stem) {
+        Ok(generated) => generated,
+        Err(e) => {
+            warn!(
+                "LEXEME STEM:\t{} has no outline for {} ({})",
+                lemma, stem, e
+            );
+            for form in forms {
+                report
+                    .failures
+                    .push(fail(form, format!("no outline for stem {:?}: {}", stem, e)));
+                add_alone(gen, lemma, form, report);
+            }
+            return Ok(());
+        }
+    };
+    debug!(
+        "LEXEME STEM:\t{} -> {} ({})",
+        lemma,
+        stem,
+        stem_chords.to_string()
+    );
+
+    for form in forms {
+        let Some(ending) = form.strip_prefix(stem.as_str()) else {
+            report
+                .failures
+                .push(fail(form, format!("does not start with stem {:?}", stem)));
+            add_alone(gen, lemma, form, report);
+            continue;
+        };
+
+        // The form itself may not make a valid word, stem + suffix still does
+        let affix_suffix = gen
+            .gen_word_chords(form)
+            .ok()
+            .and_then(|(chords, _new_chunks)| match chords.items.last() {
+                Some(ChordSeqItem::Suffix(s, _)) => Some(s.clone()),
+                _other => None,
+            });
+
+        if affix_suffix.as_deref() != Some(ending).filter(|e| !e.is_empty()) {
+            report.disagreements.push(BoundaryDisagreement {
+                lemma: lemma.to_string(),
+                form: form.clone(),
+                stem: stem.clone(),
+                ending: ending.to_string(),
+                affix_suffix,
+            });
+        }
+
+        let mut items = stem_chords.items.clone();
+
+        if !ending.is_empty() {
+            match gen.suffix_chord(ending) {
+                Some(chord) => items.push(ChordSeqItem::Suffix(ending.to_string(), chord)),
+                None => {
+                    warn!("LEXEME ENDING:\t{} has no suffix for -{}", form, ending);
+                    report
+                        .failures
+                        .push(fail(form, format!("no suffix stroke for -{}", ending)));
+                    add_alone(gen, lemma, form, report);
+                    continue;
+                }
+            }
+        }
+
+        // Only the first form records the stem's new chunks
+        gen.add_word_with_outline(
+            form,
+            ChordSequence::new(items),
+            std::mem::take(&mut new_chunks),
+        );
+        report.consistent_forms += 1;
+    }
+
+    Ok(())
+}
+
+/// Add form with an outline of its own. A form the generator rejects
+/// is reported as a failure and left out. Returns whether it was added.
+fn add_alone(gen: &mut Generator, lemma: &str, form: &str, report: &mut LexemeReport) -> bool {
+    match gen.add_word_root(form) {
+        Ok(_chords) => true,
+        Err(e) => {
+            warn!("LEXEME SKIP:\t{} ({})", form, e);
+            report.failures.push(LexemeFailure {
+                lemma: lemma.to_string(),
+                form: form.to_string(),
+                reason: format!("left out: {}", e),
+            });
+            false
+        }
+    }
+}
+
+/// One lexeme per odm line, named after its first form, which is the
+/// base form in odm.txt. Lines naming the same base form are merged.
+pub fn odm_lexemes(groups: Vec<Vec<String>>) -> Lexemes {
+    let mut lexemes = Lexemes::new();
+
+    for group in groups {
+        if let Some(lemma) = group.first() {
+            lexemes
+                .entry(lemma.clone())
+                .or_default()
+                .extend(group.iter().cloned());
+        }
+    }
+
+    lexemes
+}
+
+/// Group words by the lemma of their first interpretation. Words
+/// morph doesn't know are returned separately.
+pub fn group_by_lemma(
+    morph: &dyn Morphology,
+    words: &[String],
+) -> Result<(Lexemes, Vec<String>), ErrBox> {
+    let mut groups = Lexemes::new();
+    let mut unknown = Vec::new();
+
+    for word in words {
+        match morph.lemma(word)? {
+            Some(lemma) => {
+                groups.entry(lemma).or_default().insert(word.clone());
+            }
+            None => unknown.push(word.clone()),
+        }
+    }
+
+    Ok((groups, unknown))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::theory::Theory;
+
+    #[test]
+    fn test_choose_stem() -> Result<(), ErrBox> {
+        let gen = Generator::new(Theory::builtin())?;
+
+        let forms =
+            |words: &[&str]| -> BTreeSet<String> { words.iter().map(|s| s.to_string()).collect() };
+
+        assert_eq!(
+            choose_stem(&gen, &forms(&["kot", "kota", "kotami", "kocie"])),
+            ("kot".to_string(), 3)
+        );
+        assert_eq!(
+            choose_stem(&gen, &forms(&["czytam", "czytasz", "czytamy"])),
+            ("czyt".to_string(), 3)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_forms_share_root() -> Result<(), ErrBox> {
+        let mut gen = Generator::new(Theory::builtin())?;
+        let mut report = LexemeReport::default();
+
+        let forms: BTreeSet<String> = ["kota", "kotami", "kotem"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        add_lexeme(&mut gen, "kot", &forms, &mut report)?;
+
+        let roots: BTreeSet<String> = forms
+            .iter()
+            .map(|form| Ok(crate::generator::root_only(&gen.word_outline(form)?).print_chords()))
+            .collect::<Result<_, ErrBox>>()?;
+
+        assert_eq!(roots.len(), 1);
+        assert_eq!(report.consistent_forms, 3);
+        assert!(report.inconsistent_lexemes.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_group_by_lemma() -> Result<(), ErrBox> {
+        let mut morph = crate::morphology::TableMorphology::default();
+        morph.insert("kota", "kot", "subst:sg:gen.acc:m2");
+        morph.insert("kotem", "kot", "subst:sg:inst:m2");
+        morph.insert("psa", "pies", "subst:sg:gen.acc:m2");
+
+        let words: Vec<String> = ["kota", "psa", "kotem", "żółw"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let (groups, unknown) = group_by_lemma(&morph, &words)?;
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups["kot"].len(), 2);
+        assert_eq!(unknown, vec!["żółw"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_odm_lexemes() -> Result<(), ErrBox> {
+        let groups = crate::word_source::parse_groups(
+            "kot, kota, kotami, kotem\nbyć, jestem, jest\nkot, kotu\n",
+            crate::word_source::WordListFormat::Odm,
+        );
+
+        let lexemes = odm_lexemes(groups);
+        assert_eq!(lexemes.len(), 2);
+        assert_eq!(lexemes["kot"].len(), 5);
+
+        let mut gen = Generator::new(Theory::builtin())?;
+        let mut report = LexemeReport::default();
+
+        for (lemma, forms) in lexemes.iter() {
+            add_lexeme(&mut gen, lemma, forms, &mut report)?;
+        }
+
+        // jest/jestem share a stem, być doesn't
+        assert_eq!(report.lexemes, 2);
+        assert_eq!(report.consistent_forms, 7);
+        assert_eq!(report.inconsistent_lexemes, vec!["być"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_lexeme_is_reported() -> Result<(), ErrBox> {
+        let groups = crate::word_source::parse_groups(
+            "kot, kota, kotem\nab-a, ab-em, ab-om\npies\n",
+            crate::word_source::WordListFormat::Odm,
+        );
+
+        let mut gen = Generator::new(Theory::builtin())?;
+        let mut report = LexemeReport::default();
+
+        for (lemma, forms) in odm_lexemes(groups).iter() {
+            add_lexeme(&mut gen, lemma, forms, &mut report)?;
+        }
+
+        // The stem "ab-" isn't a word, its forms are added on their own
+        assert_eq!(report.lexemes, 3);
+        assert_eq!(report.inconsistent_lexemes, vec!["ab-a"]);
+        assert_eq!(report.failures.len(), 3);
+        assert!(report.failures.iter().all(|f| f.reason.contains("\"ab-\"")));
+
+        // The other lexemes still made it
+        assert_eq!(report.consistent_forms, 4);
+        assert!(gen.word_root_dict.contains_key(&"pies".to_string().into()));
+
+        Ok(())
+    }
+}
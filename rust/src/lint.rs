@@ -0,0 +1,425 @@
+//! Theory table lint. Finds the mistakes dict_lookup.rs has so far been
+//! checked for by hand: chords which don't parse or press invalid
+//! combinations, combos landing on the wrong hand, outlines shared by
+//! different entries and entries a word list never gets to use.
+
+use serde::Serialize;
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+};
+
+use crate::{
+    chord::{Chord, ChordSeqItem, Side},
+    generator::Generator,
+    provenance::ComboRef,
+    theory::{Theory, TheoryErrorKind},
+    ErrBox,
+};
+
+/// Tables whose entries end up in the dictionaries as whole strokes
+const DICTIONARY_SECTIONS: &[&str] = &[
+    "prefixes",
+    "suffixes",
+    "infixes",
+    "shortcuts",
+    "special_chars",
+    "commands",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintKind {
+    /// Fails Chord::from_str
+    Unparseable,
+    /// Fails Chord::validate
+    InvalidCombination,
+    /// A right-hand combo pressing left-hand keys or the other way round
+    WrongHand,
+    /// Same chord as a different entry of the same table, or of
+    /// another table ending up in the same dictionary
+    DuplicateChord,
+    /// A prefix or suffix with the same chord as a shortcut or special
+    /// character
+    AffixCollision,
+    /// Never used on the word list, longer entries always match instead
+    Shadowed,
+}
+
+impl LintKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unparseable => "unparseable",
+            Self::InvalidCombination => "invalid_combination",
+            Self::WrongHand => "wrong_hand",
+            Self::DuplicateChord => "duplicate_chord",
+            Self::AffixCollision => "affix_collision",
+            Self::Shadowed => "shadowed",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct LintIssue {
+    pub kind: LintKind,
+    pub section: &'static str,
+    pub key: String,
+    pub chord: String,
+    pub message: String,
+    /// Theory book page of the entry, see provenance::page_ref()
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<String>,
+}
+
+impl LintIssue {
+    fn new(kind: LintKind, section: &'static str, key: &str, chord: &str, message: String) -> Self {
+        Self {
+            kind,
+            section,
+            key: key.to_string(),
+            chord: chord.to_string(),
+            message,
+            page: ComboRef::new(section, key, chord).page,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct LintReport {
+    /// None if the shadowed entries check didn't run
+    pub words_checked: Option<usize>,
+    pub counts: BTreeMap<LintKind, usize>,
+    pub issues: Vec<LintIssue>,
+}
+
+impl LintReport {
+    pub fn count(&self, kind: LintKind) -> usize {
+        self.counts.get(&kind).cloned().unwrap_or(0)
+    }
+
+    pub fn extend(&mut self, issues: Vec<LintIssue>) {
+        for issue in issues {
+            *self.counts.entry(issue.kind).or_insert(0) += 1;
+            self.issues.push(issue);
+        }
+    }
+
+    pub fn save(&self, f: File) -> Result<(), ErrBox> {
+        serde_json::to_writer_pretty(f, self)?;
+        Ok(())
+    }
+}
+
+/// Checks which only need the theory itself
+pub fn lint_theory(theory: &Theory) -> Vec<LintIssue> {
+    let mut issues: Vec<LintIssue> = theory
+        .validate()
+        .into_iter()
+        .map(|e| {
+            let kind = match e.kind {
+                TheoryErrorKind::Unparseable => LintKind::Unparseable,
+                TheoryErrorKind::InvalidCombination => LintKind::InvalidCombination,
+            };
+            LintIssue::new(kind, e.section, &e.key, &e.chord, e.message)
+        })
+        .collect();
+
+    issues.extend(wrong_hand(theory));
+    issues.extend(duplicate_chords(theory));
+
+    issues
+}
+
+/// Parse a table entry the way the generator does
+fn parse_entry(section: &str, chord: &str) -> Option<Chord> {
+    match section {
+        "right_hand_combos" => format!("-{}", chord).parse().ok(),
+        _other => chord.parse().ok(),
+    }
+}
+
+fn wrong_hand(theory: &Theory) -> Vec<LintIssue> {
+    let checks = [
+        ("left_hand_combos", &theory.left_hand_combos, Side::Right),
+        ("right_hand_combos", &theory.right_hand_combos, Side::Left),
+    ];
+
+    let mut issues = Vec::new();
+
+    for (section, table, wrong_side) in checks {
+        for (key, chord) in table.iter() {
+            let Some(ch) = parse_entry(section, chord) else {
+                continue;
+            };
+
+            let wrong_keys: String = ch
+                .keys()
+                .filter(|k| k.side == wrong_side)
+                .map(|k| k.letter)
+                .collect();
+
+            if !wrong_keys.is_empty() {
+                issues.push(LintIssue::new(
+                    LintKind::WrongHand,
+                    section,
+                    key,
+                    chord,
+                    format!("parses onto {:?} keys {}", wrong_side, wrong_keys),
+                ));
+            }
+        }
+    }
+
+    issues
+}
+
+/// (section, key, chord)
+type TableEntry<'a> = (&'static str, &'a String, &'a String);
+
+/// The text an entry stands for, e.g. "prze{^}" for the prze- prefix
+fn entry_text(section: &str, key: &str) -> String {
+    match section {
+        "prefixes" => format!("{}{{^}}", key),
+        "suffixes" => format!("{{^}}{}", key),
+        "infixes" => format!("{{^{}^}}", key),
+        _other => key.to_string(),
+    }
+}
+
+fn duplicate_chords(theory: &Theory) -> Vec<LintIssue> {
+    // Combos only clash within their own table, whole strokes clash
+    // whenever they end up in the same dictionary
+    let mut groups: BTreeMap<(&str, Chord), Vec<TableEntry>> = BTreeMap::new();
+
+    for (section, table) in theory.tables() {
+        let namespace = if DICTIONARY_SECTIONS.contains(&section) {
+            "dictionary"
+        } else {
+            section
+        };
+
+        for (key, chord) in table.iter() {
+            if let Some(ch) = parse_entry(section, chord) {
+                groups
+                    .entry((namespace, ch))
+                    .or_default()
+                    .push((section, key, chord));
+            }
+        }
+    }
+
+    let is_affix = |section: &str| matches!(section, "prefixes" | "suffixes");
+    let is_word = |section: &str| matches!(section, "shortcuts" | "special_chars");
+
+    let mut issues = Vec::new();
+
+    for entries in groups.values() {
+        for (idx, entry) in entries.iter().enumerate() {
+            // Each entry is reported once, listing the earlier ones
+            let mut duplicates = Vec::new();
+
+            for other in entries[..idx].iter() {
+                if entry_text(entry.0, entry.1) == entry_text(other.0, other.1) {
+                    continue;
+                }
+
+                // Affix collisions are reported on the affix
+                let collision = if is_affix(entry.0) && is_word(other.0) {
+                    Some((entry, other))
+                } else if is_word(entry.0) && is_affix(other.0) {
+                    Some((other, entry))
+                } else {
+                    None
+                };
+
+                match collision {
+                    Some(((section, key, chord), other)) => issues.push(LintIssue::new(
+                        LintKind::AffixCollision,
+                        section,
+                        key,
+                        chord,
+                        format!("same chord as {} {:?}", other.0, other.1),
+                    )),
+                    None => duplicates.push(format!("{} {:?}", other.0, other.1)),
+                }
+            }
+
+            if !duplicates.is_empty() {
+                issues.push(LintIssue::new(
+                    LintKind::DuplicateChord,
+                    entry.0,
+                    entry.1,
+                    entry.2,
+                    format!("same chord as {}", duplicates.join(", ")),
+                ));
+            }
+        }
+    }
+
+    issues
+}
+
+/// Entries never used on words gen has been given, which a longer entry
+/// of the same table starts with (ends with, for suffixes). words are
+/// looked up with Generator::word_outline() for their affixes.
+pub fn shadowed_entries<'a>(
+    gen: &Generator,
+    words: impl IntoIterator<Item = &'a String>,
+) -> Vec<LintIssue> {
+    let mut used: BTreeSet<(&str, String)> = BTreeSet::new();
+
+    for ch_seq in gen.word_root_dict.values().chain(gen.chunk_dict.values()) {
+        for combo in gen.outline_combos(ch_seq) {
+            used.insert((combo.section, combo.text));
+        }
+    }
+
+    for word in words {
+        let Ok(outline) = gen.word_outline(word) else {
+            continue;
+        };
+
+        for item in outline.items.iter() {
+            match item {
+                ChordSeqItem::Prefix(s, _ch) => used.insert(("prefixes", s.clone())),
+                ChordSeqItem::Suffix(s, _ch) => used.insert(("suffixes", s.clone())),
+                _other => continue,
+            };
+        }
+    }
+
+    let theory = &gen.theory;
+    let tables = [
+        ("left_hand_combos", &theory.left_hand_combos),
+        ("center_combos", &theory.center_combos),
+        ("right_hand_combos", &theory.right_hand_combos),
+        ("prefixes", &theory.prefixes),
+        ("suffixes", &theory.suffixes),
+    ];
+
+    let mut issues = Vec::new();
+
+    for (section, table) in tables {
+        for (key, chord) in table.iter() {
+            if key.is_empty() || used.contains(&(section, key.clone())) {
+                continue;
+            }
+
+            let longer: Vec<&String> = table
+                .keys()
+                .filter(|other| other.len() > key.len())
+                .filter(|other| match section {
+                    "suffixes" => other.ends_with(key.as_str()),
+                    _other => other.starts_with(key.as_str()),
+                })
+                .collect();
+
+            if longer.is_empty() {
+                continue;
+            }
+
+            issues.push(LintIssue::new(
+                LintKind::Shadowed,
+                section,
+                key,
+                chord,
+                format!("never used, longer matches: {:?}", longer),
+            ));
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds_of(issues: &[LintIssue], key: &str) -> Vec<LintKind> {
+        issues
+            .iter()
+            .filter(|i| i.key == key)
+            .map(|i| i.kind)
+            .collect()
+    }
+
+    fn count_of(issues: &[LintIssue], kind: LintKind) -> usize {
+        issues.iter().filter(|i| i.kind == kind).count()
+    }
+
+    #[test]
+    fn test_lint_theory() {
+        let mut theory = Theory::builtin();
+        theory.right_hand_combos.insert("qq".into(), "K".into());
+        theory.right_hand_combos.insert("qx".into(), "CQ".into());
+        theory.suffixes.insert("qqq".into(), "XFZ".into());
+        let shortcut = theory
+            .shortcuts
+            .iter()
+            .next()
+            .map(|(k, v)| (k.clone(), v.clone()));
+        let (shortcut_word, shortcut_chord) = shortcut.unwrap();
+        theory.prefixes.insert("qqqq".into(), shortcut_chord);
+
+        let issues = lint_theory(&theory);
+
+        assert_eq!(kinds_of(&issues, "qq"), vec![LintKind::WrongHand]);
+        assert_eq!(kinds_of(&issues, "qx"), vec![LintKind::Unparseable]);
+        assert_eq!(kinds_of(&issues, "qqq"), vec![LintKind::InvalidCombination]);
+
+        let collision = issues
+            .iter()
+            .find(|i| i.key == "qqqq")
+            .expect("affix collision");
+        assert_eq!(collision.kind, LintKind::AffixCollision);
+        assert!(collision.message.contains(&format!("{:?}", shortcut_word)));
+    }
+
+    #[test]
+    fn test_shadowed_entries() -> Result<(), ErrBox> {
+        let mut theory = Theory::builtin();
+        theory.suffixes.insert("ąęąę".into(), "ZKPLAU".into());
+        theory.suffixes.insert("ęąę".into(), "ZKPLAUO".into());
+
+        let mut gen = Generator::new(theory)?;
+        let words = ["kot".to_string()];
+        for word in words.iter() {
+            gen.add_word_root(word)?;
+        }
+
+        let issues = shadowed_entries(&gen, words.iter());
+
+        assert_eq!(kinds_of(&issues, "ęąę"), vec![LintKind::Shadowed]);
+        assert!(kinds_of(&issues, "ąęąę").is_empty());
+        // Used by "kot"
+        assert!(!issues
+            .iter()
+            .any(|i| i.section == "left_hand_combos" && i.key == "k"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_shadowed_entries_with_invalid_combination() -> Result<(), ErrBox> {
+        let mut theory = Theory::builtin();
+        theory.suffixes.insert("ąęąę".into(), "ZKPLAU".into());
+        theory.suffixes.insert("ęąę".into(), "XFZ".into());
+
+        // The lint command goes on to the word list pass
+        let issues = lint_theory(&theory);
+        assert!(kinds_of(&issues, "ęąę").contains(&LintKind::InvalidCombination));
+        assert_eq!(count_of(&issues, LintKind::Unparseable), 0);
+
+        let mut gen = Generator::new(theory)?;
+        let words = ["kot".to_string()];
+        for word in words.iter() {
+            gen.add_word_root(word)?;
+        }
+
+        let issues = shadowed_entries(&gen, words.iter());
+        assert_eq!(kinds_of(&issues, "ęąę"), vec![LintKind::Shadowed]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+//! Morphological analysis behind the Morphology trait, so that the
+//! generator doesn't need the Morfeusz2 C++ library to build.
+//! MorfeuszMorphology needs the morfeusz feature, which only
+//! morfeusz/Cargo.toml turns on. TableMorphology works off an in-memory table, e.g. one loaded from
+//! a PoliMorf-style TSV dump.
+
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, Read},
+};
+
+use crate::ErrBox;
+
+/// One interpretation of a whole word
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Analysis {
+    pub lemma: String,
+    /// NKJP tag, e.g. "subst:sg:nom:m2"
+    pub tag: String,
+}
+
+impl Analysis {
+    pub fn new(lemma: &str, tag: &str) -> Self {
+        Self {
+            lemma: lemma.to_owned(),
+            tag: tag.to_owned(),
+        }
+    }
+
+    /// The part of speech, i.e. the first section of the tag
+    pub fn pos(&self) -> &str {
+        self.tag.split(':').next().unwrap_or_default()
+    }
+
+    /// Whether the tag satisfies constraint, a part of speech
+    /// optionally followed by values the tag must have, e.g.
+    /// "subst:pl". Tags list alternative values with dots, as in
+    /// "subst:sg:gen.acc:m2".
+    pub fn matches(&self, constraint: &str) -> bool {
+        let mut wanted = constraint.split(':');
+
+        if wanted.next() != Some(self.pos()) {
+            return false;
+        }
+
+        wanted.all(|value| {
+            self.tag
+                .split(':')
+                .skip(1)
+                .any(|section| section.split('.').any(|v| v == value))
+        })
+    }
+}
+
+pub trait Morphology {
+    /// Every interpretation of word as a whole, most likely first.
+    /// Empty for unknown words.
+    fn analyse(&self, word: &str) -> Result<Vec<Analysis>, ErrBox>;
+
+    /// The lemma of the first interpretation
+    fn lemma(&self, word: &str) -> Result<Option<String>, ErrBox> {
+        Ok(self.analyse(word)?.into_iter().next().map(|a| a.lemma))
+    }
+}
+
+/// Word => analyses lookup table
+#[derive(Clone, Debug, Default)]
+pub struct TableMorphology {
+    pub entries: BTreeMap<String, Vec<Analysis>>,
+}
+
+impl TableMorphology {
+    pub fn insert(&mut self, word: &str, lemma: &str, tag: &str) {
+        self.entries
+            .entry(word.to_lowercase())
+            .or_default()
+            .push(Analysis::new(lemma, tag));
+    }
+
+    /// Parse form<TAB>lemma<TAB>tag lines, as in PoliMorf. Any further
+    /// columns are ignored.
+    pub fn from_tsv(r: impl Read) -> Result<Self, ErrBox> {
+        let mut ret = Self::default();
+
+        for (idx, line) in std::io::BufReader::new(r).lines().enumerate() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let mut columns = line.split('\t');
+            match (columns.next(), columns.next(), columns.next()) {
+                (Some(word), Some(lemma), Some(tag)) => ret.insert(word, lemma, tag),
+                _other => {
+                    return Err(format!(
+                        "Line {}: expected form<TAB>lemma<TAB>tag, got {:?}",
+                        idx + 1,
+                        line
+                    )
+                    .into())
+                }
+            }
+        }
+
+        Ok(ret)
+    }
+}
+
+impl Morphology for TableMorphology {
+    fn analyse(&self, word: &str) -> Result<Vec<Analysis>, ErrBox> {
+        Ok(self
+            .entries
+            .get(&word.to_lowercase())
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(feature = "morfeusz")]
+pub struct MorfeuszMorphology {
+    morf: morfeusz2_sys::Morfeusz,
+}
+
+#[cfg(feature = "morfeusz")]
+impl MorfeuszMorphology {
+    pub fn new() -> Result<Self, ErrBox> {
+        let morf = morfeusz2_sys::Morfeusz::new(morfeusz2_sys::Settings {
+            usage: morfeusz2_sys::Usage::AnalyseOnly,
+            ..Default::default()
+        })?;
+
+        log::info!("Using Morfeusz {}", morfeusz2_sys::Morfeusz::version());
+
+        Ok(Self { morf })
+    }
+}
+
+#[cfg(feature = "morfeusz")]
+impl Morphology for MorfeuszMorphology {
+    fn analyse(&self, word: &str) -> Result<Vec<Analysis>, ErrBox> {
+        Ok(self
+            .morf
+            .analyse(word)?
+            .into_iter()
+            // Only whole-word interpretations
+            .filter(|i| {
+                !i.is_ign
+                    && i.start_node == 0
+                    && i.end_node == 1
+                    && i.orth.to_lowercase() == word.to_lowercase()
+            })
+            .map(|i| Analysis {
+                // Homonyms are numbered, e.g. "zamek:s1"
+                lemma: i.lemma.split(':').next().unwrap_or_default().to_lowercase(),
+                tag: i.tag,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_morphology() -> Result<(), ErrBox> {
+        let tsv = "kota\tkot\tsubst:sg:gen.acc:m2\tpospolita\n\
+                   Kotem\tkot\tsubst:sg:inst:m2\n\
+                   \n\
+                   kot\tkot\tsubst:sg:nom:m2\n";
+
+        let morph = TableMorphology::from_tsv(tsv.as_bytes())?;
+
+        assert_eq!(
+            morph.analyse("kotem")?,
+            vec![Analysis::new("kot", "subst:sg:inst:m2")]
+        );
+        assert_eq!(morph.lemma("KOTA")?, Some("kot".to_string()));
+        assert_eq!(morph.lemma("pies")?, None);
+
+        assert!(TableMorphology::from_tsv("kot\tkot\n".as_bytes()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_analysis_matches() {
+        let a = Analysis::new("kot", "subst:sg:gen.acc:m2");
+
+        assert!(a.matches("subst"));
+        assert!(a.matches("subst:sg:acc"));
+        assert!(!a.matches("subst:pl"));
+        assert!(!a.matches("adj"));
+        assert!(!a.matches("sub"));
+    }
+}
@@ -0,0 +1,208 @@
+//! Dictionary output formats. The generator only produces a stream of
+//! entries, every format writes them its own way:
+//!
+//! * `PloverJson` - the usual Plover JSON dictionary, pretty or compact
+//! * `PythonModule` - a Plover Python dictionary (plover-python-dictionary)
+//! * `Tsv` - one `outline<TAB>translation<TAB>source` line per entry
+//!
+//! New formats only need to implement `DictFormat`.
+
+use serde::Serialize;
+
+use std::{collections::BTreeMap, io::Write};
+
+use crate::{
+    provenance::{EntrySource, Provenance},
+    ErrBox,
+};
+
+/// Default file names of the generated dictionaries
+pub const WORD_ROOTS_FILE: &str = "word_roots.json";
+pub const SYLLABLES_FILE: &str = "syllables.json";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DictEntry {
+    pub outline: String,
+    pub translation: String,
+    pub provenance: Provenance,
+}
+
+impl DictEntry {
+    pub fn new(outline: String, translation: String, source: EntrySource) -> Self {
+        Self {
+            outline,
+            translation,
+            provenance: Provenance::new(source),
+        }
+    }
+}
+
+/// Keep the last entry for each outline, sorted by outline
+pub fn dedup_entries(entries: impl Iterator<Item = DictEntry>) -> Vec<DictEntry> {
+    let by_outline: BTreeMap<String, DictEntry> = entries.map(|e| (e.outline.clone(), e)).collect();
+
+    by_outline.into_values().collect()
+}
+
+/// Outline => translation, as Plover sees the entries
+pub fn entries_to_map(entries: &[DictEntry]) -> BTreeMap<String, String> {
+    entries
+        .iter()
+        .map(|e| (e.outline.clone(), e.translation.clone()))
+        .collect()
+}
+
+/// A way of writing dictionary entries to a file. Entries come sorted by
+/// outline, with one entry per outline.
+pub trait DictFormat {
+    fn write_entries(&self, w: &mut dyn Write, entries: &[DictEntry]) -> Result<(), ErrBox>;
+}
+
+pub struct PloverJson {
+    pub pretty: bool,
+}
+
+impl DictFormat for PloverJson {
+    fn write_entries(&self, w: &mut dyn Write, entries: &[DictEntry]) -> Result<(), ErrBox> {
+        let map = entries_to_map(entries);
+
+        if self.pretty {
+            serde_json::to_writer_pretty(w, &map)?;
+        } else {
+            serde_json::to_writer(w, &map)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct PythonModule;
+
+impl DictFormat for PythonModule {
+    fn write_entries(&self, w: &mut dyn Write, entries: &[DictEntry]) -> Result<(), ErrBox> {
+        let longest_key = entries
+            .iter()
+            .map(|e| e.outline.split('/').count())
+            .max()
+            .unwrap_or(1);
+
+        writeln!(w, "# Generated by plover-pl-dict-gen")?;
+        writeln!(w)?;
+        writeln!(w, "LONGEST_KEY = {}", longest_key)?;
+        writeln!(w)?;
+        writeln!(w, "DICTIONARY = {{")?;
+
+        // JSON string literals are valid Python string literals
+        for entry in entries.iter() {
+            writeln!(
+                w,
+                "    {}: {},",
+                serde_json::to_string(&entry.outline)?,
+                serde_json::to_string(&entry.translation)?
+            )?;
+        }
+
+        writeln!(w, "}}")?;
+        writeln!(w)?;
+        writeln!(w)?;
+        writeln!(w, "def lookup(key):")?;
+        writeln!(w, "    return DICTIONARY[\"/\".join(key)]")?;
+
+        Ok(())
+    }
+}
+
+pub struct Tsv;
+
+impl DictFormat for Tsv {
+    fn write_entries(&self, w: &mut dyn Write, entries: &[DictEntry]) -> Result<(), ErrBox> {
+        for entry in entries.iter() {
+            writeln!(
+                w,
+                "{}\t{}\t{}",
+                entry.outline,
+                escape_tsv(&entry.translation),
+                entry.provenance.source.name()
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Backslash-escape characters that would break a TSV line
+fn escape_tsv(s: &str) -> String {
+    let mut ret = String::new();
+
+    for ch in s.chars() {
+        match ch {
+            '\\' => ret.push_str("\\\\"),
+            '\t' => ret.push_str("\\t"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            ch => ret.push(ch),
+        }
+    }
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_entries() -> Vec<DictEntry> {
+        dedup_entries(
+            [
+                ("KOT", "kot", EntrySource::WordRoot),
+                ("PRE*T", "przed{^}", EntrySource::Prefix),
+                ("ZSKAU/LA", "{&szko}", EntrySource::Chunk),
+                ("KOT", "kot{^}", EntrySource::Command),
+                ("R-R", "\n", EntrySource::SpecialChar),
+            ]
+            .into_iter()
+            .map(|(o, t, s)| DictEntry::new(o.to_string(), t.to_string(), s)),
+        )
+    }
+
+    fn write(format: &dyn DictFormat) -> Result<String, ErrBox> {
+        let mut out = Vec::new();
+        format.write_entries(&mut out, &sample_entries())?;
+
+        Ok(String::from_utf8(out)?)
+    }
+
+    #[test]
+    fn test_dedup_keeps_last() {
+        let entries = sample_entries();
+
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].outline, "KOT");
+        assert_eq!(entries[0].translation, "kot{^}");
+    }
+
+    #[test]
+    fn test_formats() -> Result<(), ErrBox> {
+        let compact = write(&PloverJson { pretty: false })?;
+        assert_eq!(
+            compact,
+            r#"{"KOT":"kot{^}","PRE*T":"przed{^}","R-R":"\n","ZSKAU/LA":"{&szko}"}"#
+        );
+
+        let pretty = write(&PloverJson { pretty: true })?;
+        let reparsed: BTreeMap<String, String> = serde_json::from_str(&pretty)?;
+        assert_eq!(reparsed, entries_to_map(&sample_entries()));
+
+        let python = write(&PythonModule)?;
+        assert!(python.contains("LONGEST_KEY = 2\n"));
+        assert!(python.contains("    \"R-R\": \"\\n\",\n"));
+
+        let tsv = write(&Tsv)?;
+        assert_eq!(
+            tsv,
+            "KOT\tkot{^}\tcommand\nPRE*T\tprzed{^}\tprefix\nR-R\t\\n\tspecial_char\nZSKAU/LA\t{&szko}\tchunk\n"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,465 @@
+//! Steno-to-text translation, emulating the parts of Plover our
+//! dictionaries rely on: longest-match lookup over stroke sequences,
+//! attach (`{^}`, `{^x^}`, `{^ ^}`), glue (`{&x}`), capitalization
+//! (`{-|}`, `{*-|}`), the punctuation metas (`{.}`, `{,}`...) and the
+//! orthography rules of our system plugin for attached suffixes.
+//!
+//! Anything else in braces, e.g. `{plover:lookup}`, is treated as a
+//! command and types nothing.
+
+use lazy_static::lazy_static;
+use log::warn;
+use regex::Regex;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Read,
+};
+
+use crate::{chord::Chord, generator::Generator, plover_system::ORTHOGRAPHY_RULES, ErrBox};
+
+lazy_static! {
+    /// ORTHOGRAPHY_RULES with the Python group references rewritten
+    /// for the regex crate
+    static ref ORTHOGRAPHY: Vec<(Regex, String)> = {
+        let group_ref = Regex::new(r"\\(\d+)").unwrap();
+        ORTHOGRAPHY_RULES
+            .iter()
+            .map(|(pattern, replacement)| {
+                (
+                    Regex::new(pattern).expect("ORTHOGRAPHY_RULES must compile"),
+                    group_ref.replace_all(replacement, "$${$1}").into_owned(),
+                )
+            })
+            .collect()
+    };
+}
+
+/// Outline to translation lookup over one or more dictionaries
+pub struct StenoDictionary {
+    entries: HashMap<Vec<Chord>, String>,
+    longest_outline: usize,
+}
+
+impl StenoDictionary {
+    /// Build a dictionary out of outline => translation maps. Like in
+    /// Plover, earlier maps take priority over later ones. Unparseable
+    /// outlines are skipped with a warning.
+    pub fn from_maps(maps: &[BTreeMap<String, String>]) -> Result<Self, ErrBox> {
+        let mut entries = HashMap::new();
+
+        for map in maps.iter().rev() {
+            for (outline, translation) in map.iter() {
+                // Plover would refuse these too, e.g. "<empty>" roots
+                match parse_outline(outline) {
+                    Ok(outline) => {
+                        entries.insert(outline, translation.clone());
+                    }
+                    Err(e) => warn!("SKIP OUTLINE:\t{} => {:?}: {}", outline, translation, e),
+                }
+            }
+        }
+
+        let longest_outline = entries.keys().map(|k| k.len()).max().unwrap_or(0);
+
+        Ok(Self {
+            entries,
+            longest_outline,
+        })
+    }
+
+    /// The word_roots.json and syllables.json dictionaries of gen, in
+    /// their default Plover priority
+    pub fn from_generator(gen: &Generator) -> Result<Self, ErrBox> {
+        Self::from_maps(&[gen.word_roots_dict(), gen.syllables_dict()])
+    }
+
+    /// Parse Plover JSON dictionaries, highest priority first
+    pub fn from_json_readers(readers: Vec<impl Read>) -> Result<Self, ErrBox> {
+        let maps = readers
+            .into_iter()
+            .map(|r| serde_json::from_reader(r))
+            .collect::<Result<Vec<BTreeMap<String, String>>, _>>()?;
+
+        Self::from_maps(&maps)
+    }
+
+    pub fn get(&self, outline: &[Chord]) -> Option<&String> {
+        self.entries.get(outline)
+    }
+
+    /// Translate strokes the way Plover does when they are written one
+    /// by one: every new stroke tries to extend the translations before
+    /// it into the longest known outline.
+    pub fn translate(&self, strokes: &[Chord]) -> Vec<Translation> {
+        let mut translations: Vec<Translation> = Vec::new();
+
+        for stroke in strokes {
+            // Earliest start first, i.e. longest outline first
+            let mut first = translations.len();
+            let mut stroke_count = 1;
+            while first > 0
+                && stroke_count + translations[first - 1].strokes.len() <= self.longest_outline
+            {
+                first -= 1;
+                stroke_count += translations[first].strokes.len();
+            }
+
+            let found = (first..translations.len()).find_map(|start| {
+                let outline: Vec<Chord> = translations[start..]
+                    .iter()
+                    .flat_map(|t| t.strokes.iter().cloned())
+                    .chain(std::iter::once(*stroke))
+                    .collect();
+
+                self.get(&outline)
+                    .map(|translation| (start, outline, translation.clone()))
+            });
+
+            match found {
+                Some((start, outline, translation)) => {
+                    translations.truncate(start);
+                    translations.push(Translation {
+                        strokes: outline,
+                        text: Some(translation),
+                    });
+                }
+                None => translations.push(Translation {
+                    strokes: vec![*stroke],
+                    text: self.get(&[*stroke]).cloned(),
+                }),
+            }
+        }
+
+        translations
+    }
+
+    pub fn strokes_to_text(&self, strokes: &[Chord]) -> String {
+        format_translations(&self.translate(strokes))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Translation {
+    pub strokes: Vec<Chord>,
+    /// None for untranslated strokes
+    pub text: Option<String>,
+}
+
+/// Parse a "/"-separated outline like "PRE*T/ZSKAU"
+pub fn parse_outline(outline: &str) -> Result<Vec<Chord>, ErrBox> {
+    outline
+        .split('/')
+        .map(|stroke| {
+            stroke
+                .parse()
+                .map_err(|e| format!("Bad outline {:?}: {}", outline, e).into())
+        })
+        .collect()
+}
+
+/// One unit of output, as parsed from a translation
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Atom {
+    text: String,
+    attach_before: bool,
+    attach_after: bool,
+    /// Glued atoms attach to each other, but not to regular words
+    glue: bool,
+    cap_next: bool,
+    cap_prev: bool,
+    /// A {^suffix} meta, attached with the orthography rules
+    orthography: bool,
+}
+
+impl Atom {
+    fn text(text: &str) -> Self {
+        Self {
+            text: text.to_owned(),
+            ..Default::default()
+        }
+    }
+}
+
+fn parse_meta(meta: &str) -> Atom {
+    match meta {
+        "-|" => Atom {
+            cap_next: true,
+            ..Default::default()
+        },
+        "*-|" => Atom {
+            cap_prev: true,
+            ..Default::default()
+        },
+        "." | "?" | "!" => Atom {
+            text: meta.to_owned(),
+            attach_before: true,
+            cap_next: true,
+            ..Default::default()
+        },
+        "," | ";" | ":" => Atom {
+            text: meta.to_owned(),
+            attach_before: true,
+            ..Default::default()
+        },
+        _ => {
+            if let Some(glued) = meta.strip_prefix('&') {
+                return Atom {
+                    text: glued.to_owned(),
+                    glue: true,
+                    ..Default::default()
+                };
+            }
+
+            if let Some(stop) = meta.strip_prefix(":stop:") {
+                return Atom {
+                    text: stop.to_owned(),
+                    attach_before: true,
+                    cap_next: true,
+                    ..Default::default()
+                };
+            }
+
+            let (attach_before, rest) = match meta.strip_prefix('^') {
+                Some(rest) => (true, rest),
+                None => (false, meta),
+            };
+            let (attach_after, text) = match rest.strip_suffix('^') {
+                Some(text) => (true, text),
+                None => (false, rest),
+            };
+
+            if !attach_before && !attach_after {
+                // A command, nothing to type
+                return Atom::default();
+            }
+
+            Atom {
+                text: text.to_owned(),
+                attach_before,
+                attach_after,
+                orthography: attach_before && !attach_after && !text.trim().is_empty(),
+                ..Default::default()
+            }
+        }
+    }
+}
+
+/// Split a translation into plain text and metas. Unbalanced braces
+/// are plain text, like the "{" special char.
+fn parse_translation(translation: &str) -> Vec<Atom> {
+    let mut ret = Vec::new();
+    let mut rest = translation;
+
+    while !rest.is_empty() {
+        // A meta is a '{' with the first '}' after it, with no other '{'
+        // in between. Braces outside of one are text.
+        let meta = rest.find('{').and_then(|first_open| {
+            let close = first_open + rest[first_open..].find('}')?;
+            let open = rest[..close].rfind('{')?;
+            Some((open, close))
+        });
+
+        match meta {
+            Some((open, close)) => {
+                if open > 0 {
+                    ret.push(Atom::text(&rest[..open]));
+                }
+                ret.push(parse_meta(&rest[open + 1..close]));
+                rest = &rest[close + 1..];
+            }
+            None => {
+                ret.push(Atom::text(rest));
+                rest = "";
+            }
+        }
+    }
+
+    ret
+}
+
+/// Attach suffix to word the way Plover does: the first orthography
+/// rule matching "word ^ suffix" rewrites it, plain concatenation
+/// otherwise
+fn add_suffix(word: &str, suffix: &str) -> String {
+    let joined = format!("{} ^ {}", word, suffix);
+
+    for (re, replacement) in ORTHOGRAPHY.iter() {
+        if re.is_match(&joined) {
+            return re.replace(&joined, replacement.as_str()).into_owned();
+        }
+    }
+
+    format!("{}{}", word, suffix)
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[derive(Default)]
+struct Formatter {
+    out: String,
+    attach_next: bool,
+    cap_next: bool,
+    last_glue: bool,
+    last_word_start: usize,
+    /// Set by an empty attach, which Plover uses to keep the orthography
+    /// rules off the next suffix
+    orthography_break: bool,
+}
+
+impl Formatter {
+    fn apply(&mut self, atom: Atom) {
+        if atom.cap_prev {
+            let word = capitalize(&self.out[self.last_word_start..]);
+            self.out.replace_range(self.last_word_start.., &word);
+        }
+
+        if atom.text.is_empty() {
+            self.attach_next |= atom.attach_before || atom.attach_after;
+            self.orthography_break = atom.attach_before || atom.attach_after;
+        } else {
+            let attach = self.attach_next || atom.attach_before || (atom.glue && self.last_glue);
+
+            if !attach {
+                if !self.out.is_empty() {
+                    self.out.push(' ');
+                }
+                self.last_word_start = self.out.len();
+            }
+
+            let text = if self.cap_next {
+                capitalize(&atom.text)
+            } else {
+                atom.text.clone()
+            };
+
+            if atom.orthography && !self.orthography_break && self.last_word_start < self.out.len()
+            {
+                let word = add_suffix(&self.out[self.last_word_start..], &text);
+                self.out.replace_range(self.last_word_start.., &word);
+            } else {
+                self.out.push_str(&text);
+            }
+
+            self.attach_next = atom.attach_after;
+            self.cap_next = false;
+            self.last_glue = atom.glue;
+            self.orthography_break = false;
+        }
+
+        self.cap_next |= atom.cap_next;
+    }
+}
+
+/// Render translations into text. Untranslated strokes are written
+/// out as steno, like Plover does.
+pub fn format_translations(translations: &[Translation]) -> String {
+    let mut formatter = Formatter::default();
+
+    for t in translations {
+        match &t.text {
+            Some(text) => {
+                for atom in parse_translation(text) {
+                    formatter.apply(atom);
+                }
+            }
+            None => {
+                let steno: Vec<String> = t.strokes.iter().map(|ch| ch.to_string()).collect();
+                formatter.apply(Atom::text(&steno.join("/")));
+            }
+        }
+    }
+
+    formatter.out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::theory::Theory;
+
+    fn translations(texts: &[&str]) -> Vec<Translation> {
+        texts
+            .iter()
+            .map(|t| Translation {
+                strokes: vec![],
+                text: Some(t.to_string()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_format_translations() {
+        let cases: &[(&[&str], &str)] = &[
+            (&["ala", "ma", "kota", "{.}"], "ala ma kota."),
+            (
+                &["{-|}", "ala", "{,}", "kot", "{.}", "pies"],
+                "Ala, kot. Pies",
+            ),
+            (&["przed{^}", "szkola", "{^}kami"], "przedszkolakami"),
+            (&["{&szko}", "{&la}", "kot"], "szkola kot"),
+            (&["kot", "{&la}", "{&ta}"], "kot lata"),
+            (&["pies", "kot", "{*-|}"], "pies Kot"),
+            (&["pies", "{^ ^}", "kot"], "pies kot"),
+            (&["www", "{^.^}", "pl"], "www.pl"),
+            (&["kot", "{plover:lookup}", "pies"], "kot pies"),
+            (&["kot", "{", "}"], "kot { }"),
+            (&["a}{^}b"], "a}b"),
+            (&["a{b{^}c"], "a{bc"),
+            // Orthography applies to {^suffix} only
+            (&["woda", "{^ami}"], "wodami"),
+            (&["dobry", "{^ego}"], "dobrego"),
+            (&["kot", "{^a}"], "kota"),
+            (&["woda", "{^}ami"], "wodaami"),
+            (&["woda", "{^}", "{^ami}"], "wodaami"),
+            (&["biało", "{^-^}", "czerwona"], "biało-czerwona"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(&format_translations(&translations(input)), expected);
+        }
+    }
+
+    #[test]
+    fn test_longest_match() -> Result<(), ErrBox> {
+        let mut map = BTreeMap::new();
+        map.insert("KAU".to_owned(), "ko".to_owned());
+        map.insert("KAU/TA".to_owned(), "kota".to_owned());
+        map.insert("TA".to_owned(), "ta".to_owned());
+
+        let dict = StenoDictionary::from_maps(&[map])?;
+
+        assert_eq!(
+            dict.strokes_to_text(&parse_outline("KAU/TA/TA/KP")?),
+            "kota ta KP-"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<(), ErrBox> {
+        let mut gen = Generator::new(Theory::builtin())?;
+
+        let words = ["przedszkolakami", "kot", "żółwia", "dzieci"];
+        for word in words {
+            gen.add_word_root(word)?;
+        }
+
+        let dict = StenoDictionary::from_generator(&gen)?;
+
+        for word in words {
+            let outline = gen.word_outline(word)?;
+            assert_eq!(dict.strokes_to_text(&outline.collapse()), word);
+        }
+
+        Ok(())
+    }
+}
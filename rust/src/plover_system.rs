@@ -0,0 +1,259 @@
+//! Plover system definition. Plover needs to be told about our key
+//! layout with a system plugin module; this generates one from
+//! `chord::KEYS` so that the layout is only ever defined there.
+//!
+//! To use it, put the module in a Python package and register it as a
+//! `plover.system` entry point, e.g. in setup.cfg:
+//!
+//! ```ini
+//! [options.entry_points]
+//! plover.system =
+//!     Polish = plover_polish_system
+//! ```
+//!
+//! The generated dictionaries go in the package's `dictionaries`
+//! directory, see `DEFAULT_DICTIONARIES_ROOT`.
+
+use std::io::Write;
+
+use crate::{
+    chord::{Side, KEYS},
+    output::{SYLLABLES_FILE, WORD_ROOTS_FILE},
+    ErrBox,
+};
+
+/// Default keyboard machine mapping, one entry per `KEYS` item. Left
+/// hand keys take the qwerty top/home row pairs from q/a to t/g, right
+/// hand keys the pairs from u/j to [/'. Thumbs cover the mid keys.
+const KEYBOARD_KEYMAP: [&str; KEYS.len()] = [
+    "q", "a", "w", "s", "e", "d", "r", "f", "t", "g", // X F Z S K T P V L R
+    "c", "v", "y", "h", "n", "m", ",", // J E ~ * I A U
+    "u", "j", "i", "k", "o", "l", "p", ";", "[", "'", // C R L B S G T W O Y
+];
+
+/// Plover's orthography rules, as (regex, replacement) pairs matched
+/// against "word ^ suffix" when a {^suffix} translation is attached.
+/// Generated outlines put suffixes after a bare word root as {^}suffix,
+/// which Plover keeps out of the rules; these cover a {^suffix} written
+/// after a whole word ending in a vowel. plover_engine applies them the
+/// same way, so verify catches a rule that breaks generated words.
+pub const ORTHOGRAPHY_RULES: &[(&str, &str)] = &[
+    // woda + ami = wodami, noga + i = nogi, dobry + ego = dobrego
+    (r"^(.+)[aeoy] \^ ([aąeęioóuy].*)$", r"\1\2"),
+];
+
+pub const DEFAULT_SYSTEM_NAME: &str = "Polish";
+
+/// Plover's asset path to the dictionaries directory of the package
+/// from the module docs
+pub const DEFAULT_DICTIONARIES_ROOT: &str = "asset:plover_polish_system:dictionaries";
+
+pub struct PloverSystem {
+    pub name: String,
+    /// Where Plover looks for dictionaries
+    pub dictionaries_root: String,
+    /// Dictionary file names, highest priority first
+    pub dictionaries: Vec<String>,
+}
+
+impl Default for PloverSystem {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_SYSTEM_NAME.to_owned(),
+            dictionaries_root: DEFAULT_DICTIONARIES_ROOT.to_owned(),
+            dictionaries: vec![WORD_ROOTS_FILE.to_owned(), SYLLABLES_FILE.to_owned()],
+        }
+    }
+}
+
+/// Plover's name for the key at idx. Left hand keys and mid keys before
+/// the asterisk are written as "X-", the rest as "-C". The asterisk
+/// itself has no hyphen.
+pub fn plover_key_name(idx: usize) -> String {
+    let key = &KEYS[idx];
+    let asterisk_idx = KEYS
+        .iter()
+        .position(|k| k.letter == '*')
+        .expect("KEYS must contain the asterisk");
+
+    match key.side {
+        Side::Mid if idx == asterisk_idx => key.letter.to_string(),
+        Side::Left => format!("{}-", key.letter),
+        Side::Mid if idx < asterisk_idx => format!("{}-", key.letter),
+        Side::Mid | Side::Right => format!("-{}", key.letter),
+    }
+}
+
+/// Python string literal for s
+fn py_str(s: &str) -> String {
+    format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn py_tuple<I: IntoIterator<Item = String>>(items: I) -> String {
+    let items: Vec<String> = items.into_iter().map(|s| py_str(&s)).collect();
+
+    match items.len() {
+        1 => format!("({},)", items[0]),
+        _ => format!("({})", items.join(", ")),
+    }
+}
+
+impl PloverSystem {
+    /// Write the system as a Python module
+    pub fn write_module(&self, mut w: impl Write) -> Result<(), ErrBox> {
+        let key_names: Vec<String> = (0..KEYS.len()).map(plover_key_name).collect();
+
+        let implicit_hyphen_keys = KEYS
+            .iter()
+            .zip(key_names.iter())
+            .filter(|(key, _name)| key.side == Side::Mid)
+            .map(|(_key, name)| name.clone());
+
+        writeln!(
+            w,
+            "# {} steno system for Plover, generated by plover-pl-dict-gen",
+            self.name
+        )?;
+        writeln!(w)?;
+        writeln!(w, "KEYS = {}", py_tuple(key_names.iter().cloned()))?;
+        writeln!(w)?;
+        writeln!(
+            w,
+            "IMPLICIT_HYPHEN_KEYS = {}",
+            py_tuple(implicit_hyphen_keys)
+        )?;
+        writeln!(w)?;
+        writeln!(w, "SUFFIX_KEYS = ()")?;
+        writeln!(w)?;
+        // The layout has no number bar
+        writeln!(w, "NUMBER_KEY = None")?;
+        writeln!(w)?;
+        writeln!(w, "NUMBERS = {{}}")?;
+        writeln!(w)?;
+        writeln!(w, "FERAL_NUMBER_KEY = False")?;
+        writeln!(w)?;
+        writeln!(w, "UNDO_STROKE_STENO = {}", py_str("*"))?;
+        writeln!(w)?;
+        writeln!(w, "ORTHOGRAPHY_RULES = [")?;
+        for (pattern, replacement) in ORTHOGRAPHY_RULES.iter() {
+            writeln!(w, "    ({}, {}),", py_str(pattern), py_str(replacement))?;
+        }
+        writeln!(w, "]")?;
+        writeln!(w)?;
+        writeln!(w, "ORTHOGRAPHY_RULES_ALIASES = {{}}")?;
+        writeln!(w)?;
+        writeln!(w, "ORTHOGRAPHY_WORDLIST = None")?;
+        writeln!(w)?;
+        writeln!(w, "KEYMAPS = {{")?;
+        writeln!(w, "    'Keyboard': {{")?;
+        for (name, keyboard_key) in key_names.iter().zip(KEYBOARD_KEYMAP.iter()) {
+            writeln!(w, "        {}: {},", py_str(name), py_str(keyboard_key))?;
+        }
+        writeln!(w, "        'arpeggiate': 'space',")?;
+        writeln!(w, "        'no-op': (),")?;
+        writeln!(w, "    }},")?;
+        writeln!(w, "}}")?;
+        writeln!(w)?;
+        writeln!(w, "DICTIONARIES_ROOT = {}", py_str(&self.dictionaries_root))?;
+        writeln!(w)?;
+        writeln!(
+            w,
+            "DEFAULT_DICTIONARIES = {}",
+            py_tuple(self.dictionaries.iter().cloned())
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::chord::Chord;
+
+    /// Plover's way of joining key names into a stroke: hyphens are
+    /// dropped, and one is put before right hand keys unless an
+    /// implicit hyphen key is pressed
+    fn plover_steno(names: &[String]) -> String {
+        let implicit_hyphen = names.iter().any(|n| KEYS[key_idx(n)].side == Side::Mid);
+        let mut ret = String::new();
+
+        for name in names {
+            if name.starts_with('-') && !implicit_hyphen && !ret.contains('-') {
+                ret.push('-');
+            }
+            ret.push_str(name.trim_matches('-'));
+        }
+
+        ret
+    }
+
+    fn key_idx(name: &str) -> usize {
+        (0..KEYS.len())
+            .find(|idx| plover_key_name(*idx) == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_plover_strokes_parse_back() -> Result<(), ErrBox> {
+        for outline in [
+            "XF-", "KAUT", "PRE*T", "-CLSO", "ZSKAU", "XFKACSY", "ST-ST", "J~",
+        ] {
+            let chord: Chord = outline.parse()?;
+            let names: Vec<String> = chord.key_indices().map(plover_key_name).collect();
+
+            assert_eq!(plover_steno(&names).parse::<Chord>()?, chord, "{}", outline);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_module() -> Result<(), ErrBox> {
+        let mut out = Vec::new();
+        PloverSystem::default().write_module(&mut out)?;
+        let module = String::from_utf8(out)?;
+
+        assert!(module.contains("KEYS = ('X-', 'F-', 'Z-', 'S-', 'K-', 'T-', 'P-', 'V-', 'L-', 'R-', 'J-', 'E-', '~-', '*', '-I', '-A', '-U', '-C', '-R', '-L', '-B', '-S', '-G', '-T', '-W', '-O', '-Y')"));
+        assert!(module.contains("IMPLICIT_HYPHEN_KEYS = ('J-', 'E-', '~-', '*', '-I', '-A', '-U')"));
+        assert!(module.contains("        '-Y': '\\'',"));
+        assert!(module.contains("DEFAULT_DICTIONARIES = ('word_roots.json', 'syllables.json')"));
+        assert!(module.contains("    ('^(.+)[aeoy] \\\\^ ([aąeęioóuy].*)$', '\\\\1\\\\2'),\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_module_defines_plover_attributes() -> Result<(), ErrBox> {
+        let mut out = Vec::new();
+        PloverSystem::default().write_module(&mut out)?;
+        let module = String::from_utf8(out)?;
+
+        let defined: Vec<&str> = module
+            .lines()
+            .filter_map(|line| line.split_once(" = "))
+            .map(|(name, _value)| name)
+            .filter(|name| !name.starts_with(' '))
+            .collect();
+
+        for name in [
+            "KEYS",
+            "IMPLICIT_HYPHEN_KEYS",
+            "SUFFIX_KEYS",
+            "NUMBER_KEY",
+            "NUMBERS",
+            "UNDO_STROKE_STENO",
+            "ORTHOGRAPHY_RULES",
+            "ORTHOGRAPHY_RULES_ALIASES",
+            "ORTHOGRAPHY_WORDLIST",
+            "KEYMAPS",
+            "DICTIONARIES_ROOT",
+            "DEFAULT_DICTIONARIES",
+        ] {
+            assert!(defined.contains(&name), "{} missing", name);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,246 @@
+//! Where dictionary entries come from. Every entry knows what produced
+//! it; the details (theory combos and their pages in the theory book,
+//! syllable split, conflicts) are only worked out for the provenance
+//! sidecar, see Generator::provenance_report().
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+
+use std::{collections::BTreeMap, fs::File};
+
+use crate::{dict_lookup, output::DictEntry, ErrBox};
+
+lazy_static! {
+    /// (section, text) => page, from the "// page N" comments in dict_lookup.rs
+    static ref PAGE_REFS: BTreeMap<(String, String), String> =
+        parse_page_refs(include_str!("dict_lookup.rs"));
+}
+
+/// What produced a dictionary entry
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntrySource {
+    WordRoot,
+    Chunk,
+    Prefix,
+    Suffix,
+    Infix,
+    SpecialChar,
+    Command,
+}
+
+impl EntrySource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::WordRoot => "word_root",
+            Self::Chunk => "chunk",
+            Self::Prefix => "prefix",
+            Self::Suffix => "suffix",
+            Self::Infix => "infix",
+            Self::SpecialChar => "special_char",
+            Self::Command => "command",
+        }
+    }
+}
+
+/// How a word root got its outline
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RootSource {
+    /// Taken as is from the shortcuts section
+    Shortcut,
+    /// Built from syllable chunks of a word nothing was stripped from
+    Chunks,
+    /// Built from syllable chunks after stripping prefixes/suffixes
+    AffixStripping,
+    /// A part of a compound word
+    Compound,
+}
+
+/// Recorded by the generator for every word root
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RootProvenance {
+    pub source: RootSource,
+    /// The word the root was first generated for
+    pub word: String,
+    /// Stripped from word, e.g. "prze-" or "-ami"
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub affixes: Vec<String>,
+    /// Later words whose root was found in the dictionary as is
+    pub exact_root_hits: u64,
+    /// Moved to another outline by conflict resolution
+    pub reassigned: bool,
+}
+
+impl RootProvenance {
+    pub fn new(source: RootSource, word: &str) -> Self {
+        Self {
+            source,
+            word: word.to_string(),
+            affixes: vec![],
+            exact_root_hits: 0,
+            reassigned: false,
+        }
+    }
+}
+
+/// A theory table entry an outline relies on
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ComboRef {
+    /// Theory section, e.g. "left_hand_combos"
+    pub section: &'static str,
+    pub text: String,
+    pub chord: String,
+    /// Theory book page, only for entries unchanged from dict_lookup
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<String>,
+}
+
+impl ComboRef {
+    pub fn new(section: &'static str, text: &str, chord: &str) -> Self {
+        let page = match builtin_chord(section, text) {
+            Some(builtin) if builtin == chord => page_ref(section, text),
+            _other => None,
+        };
+
+        Self {
+            section,
+            text: text.to_string(),
+            chord: chord.to_string(),
+            page,
+        }
+    }
+}
+
+/// Where an entry comes from
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Provenance {
+    pub source: EntrySource,
+    /// Only for word roots
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root: Option<RootProvenance>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub combos: Vec<ComboRef>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub syllables: Vec<String>,
+    /// Other words generated with the same outline
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub conflicts_with: Vec<String>,
+}
+
+impl Provenance {
+    pub fn new(source: EntrySource) -> Self {
+        Self {
+            source,
+            root: None,
+            combos: vec![],
+            syllables: vec![],
+            conflicts_with: vec![],
+        }
+    }
+}
+
+/// The provenance sidecar, dictionary name => entries
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ProvenanceReport {
+    pub dictionaries: BTreeMap<String, Vec<DictEntry>>,
+}
+
+impl ProvenanceReport {
+    pub fn save(&self, f: File) -> Result<(), ErrBox> {
+        serde_json::to_writer_pretty(f, self)?;
+        Ok(())
+    }
+}
+
+/// Theory book page of a dict_lookup entry, e.g. "195, 196"
+pub fn page_ref(section: &str, text: &str) -> Option<String> {
+    PAGE_REFS
+        .get(&(section.to_string(), text.to_string()))
+        .cloned()
+}
+
+/// The chord dict_lookup has for text, as written there
+fn builtin_chord(section: &str, text: &str) -> Option<&'static str> {
+    let table = match section {
+        "left_hand_combos" => &dict_lookup::LEFT_HAND_COMBOS,
+        "center_combos" => &dict_lookup::CENTER_COMBOS,
+        "right_hand_combos" => &dict_lookup::RIGHT_HAND_COMBOS,
+        "prefixes" => &dict_lookup::PREFIXES,
+        "suffixes" => &dict_lookup::SUFFIXES,
+        "infixes" => &dict_lookup::INFIXES,
+        "shortcuts" => &dict_lookup::SHORTCUTS,
+        "special_chars" => &dict_lookup::SPECIAL_CHARS,
+        "commands" => &dict_lookup::COMMANDS,
+        _other => return None,
+    };
+
+    table.get(text).copied()
+}
+
+/// Collect the page comment above every entry of the dict_lookup tables.
+/// Tables are named after their theory section, e.g. PREFIXES is
+/// "prefixes". Commented out entries are skipped.
+fn parse_page_refs(source: &str) -> BTreeMap<(String, String), String> {
+    lazy_static! {
+        static ref TABLE_RE: Regex = Regex::new(r"^pub static ([A-Z_]+):").unwrap();
+        static ref PAGE_RE: Regex = Regex::new(r"^\s*// page ([0-9][0-9, \-]*)").unwrap();
+        static ref ENTRY_RE: Regex = Regex::new(r#"^\s*("(?:[^"\\]|\\.)*")\s*=>"#).unwrap();
+    }
+
+    let mut ret = BTreeMap::new();
+
+    let mut section = None;
+    let mut page: Option<String> = None;
+
+    for line in source.lines() {
+        if let Some(caps) = TABLE_RE.captures(line) {
+            section = Some(caps[1].to_lowercase());
+            page = None;
+        } else if let Some(caps) = PAGE_RE.captures(line) {
+            page = Some(caps[1].trim_end_matches([',', ' ', '-']).to_string());
+        } else if let Some(caps) = ENTRY_RE.captures(line) {
+            // Rust string escapes in the tables are all valid JSON
+            let (Some(section), Some(page), Ok(text)) =
+                (&section, &page, serde_json::from_str::<String>(&caps[1]))
+            else {
+                continue;
+            };
+
+            ret.insert((section.clone(), text), page.clone());
+        }
+    }
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_refs() {
+        assert_eq!(page_ref("prefixes", "przed").as_deref(), Some("195, 196"));
+        assert_eq!(page_ref("left_hand_combos", "b").as_deref(), Some("32"));
+        assert_eq!(page_ref("special_chars", "\n").as_deref(), Some("303"));
+        assert_eq!(page_ref("commands", "{-|}"), None);
+
+        let source = "pub static SUFFIXES: phf::Map<&'static str, &'static str> = phf::phf_map! {\n    \"x\" => \"A\",\n    // page 154, 155,\n    \"ami\" => \"B\",\n    // \"ach\" => \"C\", // Conflict\n};\n";
+        let refs = parse_page_refs(source);
+
+        assert_eq!(refs.len(), 1);
+        assert_eq!(
+            refs.get(&("suffixes".to_string(), "ami".to_string())),
+            Some(&"154, 155".to_string())
+        );
+    }
+
+    #[test]
+    fn test_combo_ref_page_needs_builtin_chord() {
+        let builtin = dict_lookup::PREFIXES.get("przed").unwrap();
+
+        assert!(ComboRef::new("prefixes", "przed", builtin).page.is_some());
+        assert!(ComboRef::new("prefixes", "przed", "KPL").page.is_none());
+    }
+}
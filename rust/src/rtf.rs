@@ -0,0 +1,243 @@
+//! RTF/CRE export for CAT software. Plover meta in translations is
+//! converted to RTF/CRE control words where there is an equivalent,
+//! entries using anything else are left out and reported.
+
+use serde::Serialize;
+
+use std::{collections::BTreeMap, fs::File, io::Write};
+
+use crate::ErrBox;
+
+const HEADER: &str =
+    "{\\rtf1\\ansi{\\*\\cxrev100}\\cxdict{\\*\\cxsystem plover-pl-dict-gen}{\\stylesheet{\\s0 Normal;}}\r\n";
+
+/// Plover punctuation metas, written with \cxp
+const PUNCTUATION: &[&str] = &[".", ",", "?", "!", ";", ":"];
+
+/// An entry left out of the RTF/CRE dictionary
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RtfSkipped {
+    pub outline: String,
+    pub translation: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RtfReport {
+    pub written: usize,
+    pub skipped: Vec<RtfSkipped>,
+}
+
+impl RtfReport {
+    pub fn save(&self, f: File) -> Result<(), ErrBox> {
+        serde_json::to_writer_pretty(f, self)?;
+        Ok(())
+    }
+}
+
+/// Write outline => translation entries as an RTF/CRE dictionary
+pub fn write_rtf(
+    mut w: impl Write,
+    entries: &BTreeMap<String, String>,
+) -> Result<RtfReport, ErrBox> {
+    let mut report = RtfReport::default();
+
+    w.write_all(HEADER.as_bytes())?;
+
+    for (outline, translation) in entries.iter() {
+        match convert_translation(translation) {
+            Ok(rtf) => {
+                write!(w, "{{\\*\\cxs {}}}{}\r\n", outline, rtf)?;
+                report.written += 1;
+            }
+            Err(reason) => report.skipped.push(RtfSkipped {
+                outline: outline.clone(),
+                translation: translation.clone(),
+                reason,
+            }),
+        }
+    }
+
+    w.write_all(b"}\r\n")?;
+
+    Ok(report)
+}
+
+/// Convert a Plover translation to RTF/CRE. Err holds the reason if
+/// some part of it has no RTF/CRE equivalent.
+pub fn convert_translation(translation: &str) -> Result<String, String> {
+    // Placeholders like "<new page>" and "<speaker1>"
+    if translation.len() > 2 && translation.starts_with('<') && translation.ends_with('>') {
+        return Err(format!(
+            "placeholder {} has no RTF/CRE equivalent",
+            translation
+        ));
+    }
+
+    let mut ret = String::new();
+    let mut rest = translation;
+
+    while !rest.is_empty() {
+        // A brace without a closing one is plain text, e.g. "{"
+        let meta = rest
+            .strip_prefix('{')
+            .and_then(|after| after.find('}').map(|end| &after[..end]));
+
+        match meta {
+            Some(meta) => {
+                ret.push_str(&convert_meta(meta)?);
+                rest = &rest[meta.len() + 2..];
+            }
+            None => {
+                let first_len = rest.chars().next().map(char::len_utf8).unwrap_or(1);
+                let text_end = rest[first_len..]
+                    .find('{')
+                    .map(|idx| idx + first_len)
+                    .unwrap_or(rest.len());
+                ret.push_str(&escape(&rest[..text_end]));
+                rest = &rest[text_end..];
+            }
+        }
+    }
+
+    Ok(ret)
+}
+
+/// Convert the inside of a Plover meta, e.g. "^" for "{^}"
+fn convert_meta(meta: &str) -> Result<String, String> {
+    const DELETE_SPACE: &str = "{\\cxds}";
+
+    if meta == "^" {
+        return Ok(DELETE_SPACE.to_string());
+    }
+
+    if let Some(text) = meta.strip_prefix('&') {
+        return Ok(format!("{{\\cxfing {}}}", escape(text)));
+    }
+
+    if meta == "-|" {
+        return Ok("{\\cxfc}".to_string());
+    }
+
+    if PUNCTUATION.contains(&meta) {
+        return Ok(format!("{{\\cxp{} }}", escape(meta)));
+    }
+
+    if let Some(text) = meta.strip_prefix(":stop:") {
+        return Ok(format!("{{\\cxp{} }}", escape(text)));
+    }
+
+    // Attached text, e.g. "^.^" or "^o-^"
+    let (attach_before, text) = match meta.strip_prefix('^') {
+        Some(text) => (true, text),
+        None => (false, meta),
+    };
+    let (attach_after, text) = match text.strip_suffix('^') {
+        Some(text) => (true, text),
+        None => (false, text),
+    };
+
+    if (attach_before || attach_after) && !text.contains(['{', '}', '^', '|', ':', '#']) {
+        return Ok(format!(
+            "{}{}{}",
+            if attach_before { DELETE_SPACE } else { "" },
+            escape(text),
+            if attach_after { DELETE_SPACE } else { "" }
+        ));
+    }
+
+    Err(format!("{{{}}} has no RTF/CRE equivalent", meta))
+}
+
+/// Escape plain text, writing non-ASCII characters as \uN?
+fn escape(text: &str) -> String {
+    let mut ret = String::new();
+
+    for ch in text.chars() {
+        match ch {
+            '\\' | '{' | '}' => {
+                ret.push('\\');
+                ret.push(ch);
+            }
+            '\n' => ret.push_str("\\par "),
+            '\t' => ret.push_str("\\tab "),
+            ch if ch.is_ascii() => ret.push(ch),
+            ch => {
+                let mut units = [0u16; 2];
+                for unit in ch.encode_utf16(&mut units) {
+                    ret.push_str(&format!("\\u{}?", *unit as i16));
+                }
+            }
+        }
+    }
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_translation() {
+        let cases: &[(&str, &str)] = &[
+            ("kot", "kot"),
+            ("żółw", "\\u380?\\u243?\\u322?w"),
+            ("przed{^}", "przed{\\cxds}"),
+            ("{^}kami", "{\\cxds}kami"),
+            ("{^o-^}", "{\\cxds}o-{\\cxds}"),
+            ("{^ ^}", "{\\cxds} {\\cxds}"),
+            ("{&szko}", "{\\cxfing szko}"),
+            ("{-|}", "{\\cxfc}"),
+            ("{.}", "{\\cxp. }"),
+            ("{:stop:...}", "{\\cxp... }"),
+            ("{", "\\{"),
+            ("\\", "\\\\"),
+            ("\n", "\\par "),
+        ];
+
+        for (translation, expected) in cases {
+            assert_eq!(
+                convert_translation(translation).as_deref(),
+                Ok(*expected),
+                "{:?}",
+                translation
+            );
+        }
+
+        for translation in ["{*-|}", "{plover:lookup}", "<new page>"] {
+            assert!(
+                convert_translation(translation).is_err(),
+                "{:?}",
+                translation
+            );
+        }
+    }
+
+    #[test]
+    fn test_write_rtf() -> Result<(), ErrBox> {
+        let entries: BTreeMap<String, String> = [
+            ("KOT", "kot"),
+            ("JIAU", "{plover:lookup}"),
+            ("PRE*T", "przed{^}"),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        let mut out = Vec::new();
+        let report = write_rtf(&mut out, &entries)?;
+        let out = String::from_utf8(out)?;
+
+        assert!(out.starts_with("{\\rtf1\\ansi"));
+        assert!(out.contains("{\\*\\cxs KOT}kot\r\n"));
+        assert!(out.contains("{\\*\\cxs PRE*T}przed{\\cxds}\r\n"));
+        assert!(out.ends_with("}\r\n"));
+
+        assert_eq!(report.written, 2);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].outline, "JIAU");
+
+        Ok(())
+    }
+}
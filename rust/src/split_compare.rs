@@ -0,0 +1,134 @@
+//! Stroke counts of the stroke-aware split strategy against a
+//! syllable-based one, word by word.
+
+use serde::Serialize;
+
+use std::fs::File;
+
+use crate::{
+    generator::{Generator, SplitStrategy},
+    ErrBox,
+};
+
+/// A word whose outline length differs between the strategies
+#[derive(Clone, Debug, Serialize)]
+pub struct SplitDifference {
+    pub word: String,
+    pub baseline_outline: String,
+    pub stroke_aware_outline: String,
+    /// Positive if the stroke-aware outline is shorter
+    pub strokes_saved: isize,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SplitComparison {
+    pub baseline: String,
+    pub compared: usize,
+    /// Words either strategy has no outline for
+    pub rejected: Vec<String>,
+    pub baseline_strokes: usize,
+    pub stroke_aware_strokes: usize,
+    /// Shorter with the stroke-aware strategy
+    pub improved: usize,
+    /// Longer with the stroke-aware strategy
+    pub worsened: usize,
+    /// Largest savings first
+    pub differences: Vec<SplitDifference>,
+}
+
+impl SplitComparison {
+    pub fn strokes_saved(&self) -> isize {
+        self.baseline_strokes as isize - self.stroke_aware_strokes as isize
+    }
+
+    pub fn save(&self, f: File) -> Result<(), ErrBox> {
+        serde_json::to_writer_pretty(f, self)?;
+        Ok(())
+    }
+}
+
+/// Outline every word with baseline and with SplitStrategy::StrokeAware.
+/// gen keeps its own split strategy afterwards.
+pub fn compare_words<'a>(
+    gen: &mut Generator,
+    baseline: SplitStrategy,
+    words: impl Iterator<Item = &'a String>,
+) -> SplitComparison {
+    let original_strategy = gen.split_strategy;
+
+    let mut ret = SplitComparison {
+        baseline: format!("{:?}", baseline),
+        ..Default::default()
+    };
+
+    for word in words {
+        gen.split_strategy = baseline;
+        let baseline_chords = gen.gen_word_chords(word);
+
+        gen.split_strategy = SplitStrategy::StrokeAware;
+        let stroke_aware_chords = gen.gen_word_chords(word);
+
+        let (Ok((baseline_chords, _)), Ok((stroke_aware_chords, _))) =
+            (baseline_chords, stroke_aware_chords)
+        else {
+            ret.rejected.push(word.clone());
+            continue;
+        };
+
+        let baseline_len = baseline_chords.items.len();
+        let stroke_aware_len = stroke_aware_chords.items.len();
+
+        ret.compared += 1;
+        ret.baseline_strokes += baseline_len;
+        ret.stroke_aware_strokes += stroke_aware_len;
+
+        if baseline_len != stroke_aware_len {
+            if stroke_aware_len < baseline_len {
+                ret.improved += 1;
+            } else {
+                ret.worsened += 1;
+            }
+
+            ret.differences.push(SplitDifference {
+                word: word.clone(),
+                baseline_outline: baseline_chords.print_chords(),
+                stroke_aware_outline: stroke_aware_chords.print_chords(),
+                strokes_saved: baseline_len as isize - stroke_aware_len as isize,
+            });
+        }
+    }
+
+    ret.differences
+        .sort_by(|a, b| (b.strokes_saved, &a.word).cmp(&(a.strokes_saved, &b.word)));
+
+    gen.split_strategy = original_strategy;
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::theory::Theory;
+
+    #[test]
+    fn test_compare_words() -> Result<(), ErrBox> {
+        let mut gen = Generator::new(Theory::builtin())?;
+
+        let words: Vec<String> = ["dziewczyna", "kot"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let report = compare_words(&mut gen, SplitStrategy::Rough, words.iter());
+
+        assert_eq!(report.compared, 2);
+        assert_eq!(report.strokes_saved(), 1);
+        assert_eq!(report.improved, 1);
+        assert_eq!(report.differences[0].word, "dziewczyna");
+        assert_eq!(gen.split_strategy, SplitStrategy::Rough);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,477 @@
+//! Polish syllabification by the maximal-onset principle: consonants
+//! between two vowels go to the following syllable as long as they
+//! form a permissible onset, the rest closes the preceding syllable.
+//!
+//! Digraphs (ch, cz, dz, dź, dż, rz, sz) are single consonants, and an
+//! "i" between a consonant and a vowel only softens the consonant
+//! (e.g. "sio" in "siostra"), it doesn't make a syllable of its own.
+
+/// Permissible onsets of two or more consonants, written with soft
+/// consonants as ś/ć/ź/ń/dź and softened ones as their base letter.
+/// Mostly word-initial clusters, leaving out the ones starting with a
+/// sonorant (rt, łk, mg...) so that sonorants stay in the coda, as in
+/// kar-ta or weł-na, and a few rare ones (kt, tk, pt...) or ones with
+/// a stop and n (dn, kn...), which would give splits like ma-tka or
+/// lo-tni-sko. cj, sj and zj are there for loanwords like "instrukcja".
+static ONSETS: phf::Set<&'static str> = phf::phf_set! {
+    // b
+    "bl", "bł", "br", "brz", "bz", "bzd", "bż",
+    // c
+    "cj", "cl", "cm", "cw", "cł",
+    // ć
+    "ćm", "ćw",
+    // ch
+    "chl", "chł", "chm", "chr", "chrz", "chw", "chc",
+    // cz
+    "czm", "czt", "czw", "czł", "czc",
+    // d
+    "dl", "dł", "dm", "dr", "drw", "drż", "dw",
+    // dz, dź, dż
+    "dzb", "dzw", "dźw", "dżdż",
+    // f
+    "fl", "fr",
+    // g
+    "gl", "gł", "gm", "gr", "grz", "gw", "gż",
+    // h
+    "hr", "hl",
+    // k
+    "kl", "kł", "km", "kr", "krz", "krw", "krt", "ks", "ksz", "kszt", "kś", "kw",
+    // p
+    "pl", "pł", "pr", "prz", "ps", "pszcz", "pś", "pst", "pstr", "pch", "pchł",
+    // s
+    "sf", "sj", "sk", "skl", "skł", "skr", "skrz", "skw", "sł", "sm", "sn", "sp", "spl", "spł", "spr",
+    "st", "str", "strz", "stw", "sw", "sch", "schl", "schn", "schr",
+    // ś
+    "śl", "śm", "śn", "śń", "śp", "śr", "św", "ść", "śmr",
+    // sz
+    "szk", "szl", "szł", "szm", "szn", "szp", "szr", "szt", "sztr", "szw", "szcz", "szczw",
+    // t
+    "tl", "tł", "tr", "trz", "trw", "tw", "tch",
+    // w
+    "wł", "wr", "wrz", "wp", "wpr", "wprz", "ws", "wsch", "wsk", "wskr", "wsp", "wspr", "wst", "wstr",
+    "wsz", "wszcz", "wś", "wt", "wtr", "wz", "wzb", "wzg", "wzm", "wzn", "wzr",
+    // z
+    "zb", "zbr", "zj", "zd", "zdr", "zg", "zgl", "zgł", "zgn", "zgr", "zł", "zm", "zn", "zr", "zw", "zdz",
+    // ź
+    "źd", "źdź", "źr",
+    // ż
+    "żb", "żd", "żdż", "żł", "żm", "żr", "żw",
+};
+
+pub const VOWELS: &str = "aąeęioóuy";
+
+/// Two-letter consonants, longest first. "dzi" is handled separately.
+const DIGRAPHS: &[&str] = &["ch", "cz", "dz", "dź", "dż", "rz", "sz"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum UnitKind {
+    Consonant,
+    Vowel,
+}
+
+/// A consonant or vowel of word, with its byte range
+#[derive(Clone, Copy, Debug)]
+struct Unit {
+    kind: UnitKind,
+    start: usize,
+    end: usize,
+    /// A consonant followed by an i which is a vowel of its own, but
+    /// still softens it (the "ci" of "wiadomości")
+    softened: bool,
+}
+
+fn is_vowel(c: char) -> bool {
+    VOWELS.contains(c)
+}
+
+/// Split word into consonants (digraphs and softening i included) and
+/// vowels (au/eu counted as one)
+fn units(word: &str) -> Vec<Unit> {
+    let chars: Vec<(usize, char)> = word.char_indices().collect();
+    let byte_at = |idx: usize| chars.get(idx).map(|(b, _c)| *b).unwrap_or(word.len());
+    let char_at = |idx: usize| chars.get(idx).map(|(_b, c)| *c);
+
+    let mut ret = Vec::new();
+    let mut idx = 0;
+
+    while idx < chars.len() {
+        let c = chars[idx].1;
+        let start = byte_at(idx);
+
+        if is_vowel(c) {
+            let len = match (c, char_at(idx + 1)) {
+                ('a' | 'e', Some('u')) => 2,
+                _other => 1,
+            };
+
+            ret.push(Unit {
+                kind: UnitKind::Vowel,
+                start,
+                end: byte_at(idx + len),
+                softened: false,
+            });
+            idx += len;
+            continue;
+        }
+
+        let mut len = DIGRAPHS
+            .iter()
+            .find(|digraph| word[start..].starts_with(*digraph))
+            .map(|digraph| digraph.chars().count())
+            .unwrap_or(1);
+
+        // i before a vowel only softens the consonant
+        let softening_i =
+            char_at(idx + len) == Some('i') && char_at(idx + len + 1).is_some_and(is_vowel);
+        if softening_i {
+            len += 1;
+        }
+
+        ret.push(Unit {
+            kind: UnitKind::Consonant,
+            start,
+            end: byte_at(idx + len),
+            softened: !softening_i && char_at(idx + len) == Some('i'),
+        });
+        idx += len;
+    }
+
+    ret
+}
+
+/// How a consonant is written in ONSETS
+fn onset_key(consonant: &str) -> &str {
+    match consonant {
+        "si" => "ś",
+        "ci" => "ć",
+        "zi" => "ź",
+        "ni" => "ń",
+        "dzi" => "dź",
+        other => other.strip_suffix('i').unwrap_or(other),
+    }
+}
+
+fn is_onset(word: &str, consonants: &[Unit]) -> bool {
+    if consonants.len() < 2 {
+        return true;
+    }
+
+    let cluster: String = consonants
+        .iter()
+        .map(|u| match u.softened {
+            true => onset_key(&format!("{}i", &word[u.start..u.end])).to_owned(),
+            false => onset_key(&word[u.start..u.end]).to_owned(),
+        })
+        .collect();
+
+    ONSETS.contains(cluster.as_str())
+}
+
+/// Split a lowercase word into syllables. Words without vowels are a
+/// single syllable.
+pub fn syllabify(word: &str) -> Vec<String> {
+    let units = units(word);
+
+    let vowel_idxs: Vec<usize> = units
+        .iter()
+        .enumerate()
+        .filter(|(_idx, u)| u.kind == UnitKind::Vowel)
+        .map(|(idx, _u)| idx)
+        .collect();
+
+    if vowel_idxs.is_empty() {
+        return vec![word.to_owned()];
+    }
+
+    let mut ret = Vec::new();
+    let mut syllable_start = 0;
+
+    for pair in vowel_idxs.windows(2) {
+        let consonants = &units[pair[0] + 1..pair[1]];
+
+        // The longest tail of the cluster which can start a syllable
+        let coda_len = (0..consonants.len())
+            .find(|len| is_onset(word, &consonants[*len..]))
+            .unwrap_or(consonants.len());
+
+        let boundary = consonants
+            .get(coda_len)
+            .map(|u| u.start)
+            .unwrap_or(units[pair[1]].start);
+
+        ret.push(word[syllable_start..boundary].to_owned());
+        syllable_start = boundary;
+    }
+
+    ret.push(word[syllable_start..].to_owned());
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_syllabify_golden() {
+        let cases: &[(&str, &str)] = &[
+            ("przebiegłość", "prze-bie-głość"),
+            ("wyniosły", "wy-nio-sły"),
+            ("aorta", "a-or-ta"),
+            ("towot", "to-wot"),
+            ("dodekahedron", "do-de-ka-he-dron"),
+            ("kościół", "ko-ściół"),
+            ("zawżdy", "zaw-żdy"),
+            ("spółgłoska", "spół-gło-ska"),
+            ("kuchta", "kuch-ta"),
+            ("marzanna", "ma-rzan-na"),
+            ("marznąć", "marz-nąć"),
+            ("siostra", "sio-stra"),
+            ("ciocia", "cio-cia"),
+            ("ziemia", "zie-mia"),
+            ("niebo", "nie-bo"),
+            ("dziecko", "dziec-ko"),
+            ("dzieci", "dzie-ci"),
+            ("dziadek", "dzia-dek"),
+            ("dźwig", "dźwig"),
+            ("dżem", "dżem"),
+            ("dżungla", "dżun-gla"),
+            ("rzeka", "rze-ka"),
+            ("morze", "mo-rze"),
+            ("szkoła", "szko-ła"),
+            ("czapka", "czap-ka"),
+            ("chleb", "chleb"),
+            ("kaczka", "kacz-ka"),
+            ("ryba", "ry-ba"),
+            ("mama", "ma-ma"),
+            ("tata", "ta-ta"),
+            ("woda", "wo-da"),
+            ("ręka", "rę-ka"),
+            ("noga", "no-ga"),
+            ("okno", "ok-no"),
+            ("krzesło", "krze-sło"),
+            ("stół", "stół"),
+            ("miasto", "mia-sto"),
+            ("państwo", "pań-stwo"),
+            ("jabłko", "jabł-ko"),
+            ("zdjęcie", "zdję-cie"),
+            ("wszystko", "wszyst-ko"),
+            ("powstanie", "po-wsta-nie"),
+            ("nawzajem", "na-wza-jem"),
+            ("nawzaj", "na-wzaj"),
+            ("odwzorować", "od-wzo-ro-wać"),
+            ("historia", "hi-sto-ria"),
+            ("historii", "hi-sto-rii"),
+            ("chemia", "che-mia"),
+            ("fizyka", "fi-zy-ka"),
+            ("biologia", "bio-lo-gia"),
+            ("piosenka", "pio-sen-ka"),
+            ("wiosna", "wio-sna"),
+            ("jesień", "je-sień"),
+            ("zima", "zi-ma"),
+            ("lato", "la-to"),
+            ("pociąg", "po-ciąg"),
+            ("samochód", "sa-mo-chód"),
+            ("przedszkole", "przed-szko-le"),
+            ("przedszkolakami", "przed-szko-la-ka-mi"),
+            ("żółwia", "żół-wia"),
+            ("kot", "kot"),
+            ("pies", "pies"),
+            ("pszczoła", "pszczo-ła"),
+            ("chrząszcz", "chrząszcz"),
+            ("źdźbło", "źdźbło"),
+            ("mgła", "mgła"),
+            ("mleko", "mle-ko"),
+            ("łza", "łza"),
+            ("wróbel", "wró-bel"),
+            ("matka", "mat-ka"),
+            ("córka", "cór-ka"),
+            ("siostrzeniec", "sio-strze-niec"),
+            ("ojczyzna", "oj-czy-zna"),
+            ("królestwo", "kró-le-stwo"),
+            ("zwierzę", "zwie-rzę"),
+            ("zwierzęta", "zwie-rzę-ta"),
+            ("gwiazda", "gwia-zda"),
+            ("gwiazdka", "gwiazd-ka"),
+            ("szczęście", "szczę-ście"),
+            ("dzwon", "dzwon"),
+            ("dzwonek", "dzwo-nek"),
+            ("dżdżownica", "dżdżow-ni-ca"),
+            ("drzewo", "drze-wo"),
+            ("drzwi", "drzwi"),
+            ("trzeba", "trze-ba"),
+            ("trzy", "trzy"),
+            ("krzyk", "krzyk"),
+            ("brzeg", "brzeg"),
+            ("grzyb", "grzyb"),
+            ("wrzesień", "wrze-sień"),
+            ("kwiat", "kwiat"),
+            ("kwiatek", "kwia-tek"),
+            ("światło", "świa-tło"),
+            ("światła", "świa-tła"),
+            ("ściana", "ścia-na"),
+            ("śnieg", "śnieg"),
+            ("śmiech", "śmiech"),
+            ("ślub", "ślub"),
+            ("środa", "śro-da"),
+            ("wstęp", "wstęp"),
+            ("wzgląd", "wzgląd"),
+            ("nauka", "nau-ka"),
+            ("auto", "au-to"),
+            ("euro", "eu-ro"),
+            ("pauza", "pau-za"),
+            ("karta", "kar-ta"),
+            ("wełna", "weł-na"),
+            ("kolumna", "ko-lum-na"),
+            ("hamburger", "ham-bur-ger"),
+            ("kontrakt", "kon-trakt"),
+            ("obiekt", "o-biekt"),
+            ("projekt", "pro-jekt"),
+            ("aspekt", "a-spekt"),
+            ("perspektywa", "per-spek-ty-wa"),
+            ("instrukcja", "in-struk-cja"),
+            ("konstrukcja", "kon-struk-cja"),
+            ("czytać", "czy-tać"),
+            ("czytam", "czy-tam"),
+            ("czytasz", "czy-tasz"),
+            ("czytamy", "czy-ta-my"),
+            ("pisać", "pi-sać"),
+            ("piszę", "pi-szę"),
+            ("piszemy", "pi-sze-my"),
+            ("mówić", "mó-wić"),
+            ("mówię", "mó-wię"),
+            ("mówimy", "mó-wi-my"),
+            ("robić", "ro-bić"),
+            ("robię", "ro-bię"),
+            ("zadanie", "za-da-nie"),
+            ("pytanie", "py-ta-nie"),
+            ("mieszkanie", "mie-szka-nie"),
+            ("spotkanie", "spot-ka-nie"),
+            ("wydanie", "wy-da-nie"),
+            ("wydarzenie", "wy-da-rze-nie"),
+            ("przedsiębiorstwo", "przed-się-bior-stwo"),
+            ("warszawa", "war-sza-wa"),
+            ("kraków", "kra-ków"),
+            ("gdańsk", "gdańsk"),
+            ("wrocław", "wro-cław"),
+            ("poznań", "po-znań"),
+            ("łódź", "łódź"),
+            ("szczecin", "szcze-cin"),
+            ("lublin", "lu-blin"),
+            ("białystok", "bia-ły-stok"),
+            ("ekstra", "ek-stra"),
+            ("abstrakcja", "ab-strak-cja"),
+            ("rozmowa", "ro-zmo-wa"),
+            ("rozpoczęcie", "roz-po-czę-cie"),
+            ("bezpieczeństwo", "bez-pie-czeń-stwo"),
+            ("niebezpieczny", "nie-bez-piecz-ny"),
+            ("telewizja", "te-le-wi-zja"),
+            ("sesja", "se-sja"),
+            ("poezja", "po-e-zja"),
+            ("akcja", "ak-cja"),
+            ("optyka", "op-ty-ka"),
+            ("doktor", "dok-tor"),
+            ("równy", "rów-ny"),
+            ("strzała", "strza-ła"),
+            ("zamek", "za-mek"),
+            ("zamku", "zam-ku"),
+            ("pociągu", "po-cią-gu"),
+            ("wiatr", "wiatr"),
+            ("wiatru", "wia-tru"),
+            ("piątek", "pią-tek"),
+            ("środek", "śro-dek"),
+            ("środku", "środ-ku"),
+            ("miód", "miód"),
+            ("pięć", "pięć"),
+            ("dziewięć", "dzie-więć"),
+            ("siedem", "sie-dem"),
+            ("osiem", "o-siem"),
+            ("dziesięć", "dzie-sięć"),
+            ("dobry", "do-bry"),
+            ("dobrze", "do-brze"),
+            ("źle", "źle"),
+            ("lepiej", "le-piej"),
+            ("najlepszy", "naj-lep-szy"),
+            ("gorszy", "gor-szy"),
+            ("cukier", "cu-kier"),
+            ("cukru", "cu-kru"),
+            ("mąka", "mą-ka"),
+            ("masło", "ma-sło"),
+            ("chleba", "chle-ba"),
+            ("jajko", "jaj-ko"),
+            ("jajka", "jaj-ka"),
+            ("poniedziałek", "po-nie-dzia-łek"),
+            ("wtorek", "wto-rek"),
+            ("czwartek", "czwar-tek"),
+            ("sobota", "so-bo-ta"),
+            ("niedziela", "nie-dzie-la"),
+            ("styczeń", "sty-czeń"),
+            ("luty", "lu-ty"),
+            ("marzec", "ma-rzec"),
+            ("kwiecień", "kwie-cień"),
+            ("maj", "maj"),
+            ("czerwiec", "czer-wiec"),
+            ("lipiec", "li-piec"),
+            ("sierpień", "sier-pień"),
+            ("październik", "pa-ździer-nik"),
+            ("listopad", "li-sto-pad"),
+            ("grudzień", "gru-dzień"),
+            ("ptak", "ptak"),
+            ("ptaki", "pta-ki"),
+            ("zwierzątko", "zwie-rząt-ko"),
+            ("wiewiórka", "wie-wiór-ka"),
+            ("niedźwiedź", "nie-dźwiedź"),
+            ("lis", "lis"),
+            ("wilk", "wilk"),
+            ("jeż", "jeż"),
+            ("sowa", "so-wa"),
+            ("orzeł", "o-rzeł"),
+            ("jaskółka", "ja-skół-ka"),
+            ("komputer", "kom-pu-ter"),
+            ("klawiatura", "kla-wia-tu-ra"),
+            ("myszka", "my-szka"),
+            ("ekran", "e-kran"),
+            ("drukarka", "dru-kar-ka"),
+            ("program", "pro-gram"),
+            ("internet", "in-ter-net"),
+            ("telefon", "te-le-fon"),
+            ("budynek", "bu-dy-nek"),
+            ("mieszkańcy", "mie-szkań-cy"),
+            ("ulica", "u-li-ca"),
+            ("skrzyżowanie", "skrzy-żo-wa-nie"),
+            ("autobus", "au-to-bus"),
+            ("tramwaj", "tram-waj"),
+            ("lotnisko", "lot-ni-sko"),
+            ("dworzec", "dwo-rzec"),
+            ("jedna", "jed-na"),
+            ("ogień", "o-gień"),
+            ("ogniska", "og-ni-ska"),
+            ("ławka", "ław-ka"),
+            ("łapka", "łap-ka"),
+            ("kupny", "kup-ny"),
+            ("sukienka", "su-kien-ka"),
+            ("wiadomość", "wia-do-mość"),
+            ("wiadomości", "wia-do-mo-ści"),
+            ("miłości", "mi-ło-ści"),
+            ("pieśni", "pie-śni"),
+            ("wiśnia", "wi-śnia"),
+            ("pieniądze", "pie-nią-dze"),
+            ("człowiek", "czło-wiek"),
+            ("ludzie", "lu-dzie"),
+            ("dziewczyna", "dziew-czy-na"),
+            ("chłopiec", "chło-piec"),
+            ("mężczyzna", "męż-czy-zna"),
+            ("kobieta", "ko-bie-ta"),
+        ];
+
+        let failures: Vec<String> = cases
+            .iter()
+            .filter_map(|(word, expected)| {
+                let got = syllabify(word).join("-");
+                (got != *expected).then(|| format!("{}: expected {}, got {}", word, expected, got))
+            })
+            .collect();
+
+        assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+    }
+}
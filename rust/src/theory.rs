@@ -0,0 +1,328 @@
+//! Steno theory tables. The built-in theory comes from dict_lookup, but
+//! any section can be replaced at runtime with a JSON theory file:
+//!
+//! ```json
+//! {
+//!     "left_hand_combos": {"b": "PJ"},
+//!     "prefix_exceptions": ["pralina"]
+//! }
+//! ```
+//!
+//! Sections missing from the file keep their built-in contents.
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, File},
+    path::Path,
+};
+
+use crate::{chord::Chord, dict_lookup, ErrBox};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Theory {
+    pub left_hand_combos: BTreeMap<String, String>,
+    pub center_combos: BTreeMap<String, String>,
+    /// Written without the leading hyphen, e.g. "BT" for "-BT"
+    pub right_hand_combos: BTreeMap<String, String>,
+    pub prefixes: BTreeMap<String, String>,
+    pub suffixes: BTreeMap<String, String>,
+    /// Interfixes of compound words, see dict_lookup::INFIXES
+    pub infixes: BTreeMap<String, String>,
+    pub shortcuts: BTreeMap<String, String>,
+    pub special_chars: BTreeMap<String, String>,
+    pub commands: BTreeMap<String, String>,
+    pub prefix_exceptions: BTreeSet<String>,
+    pub suffix_exceptions: BTreeSet<String>,
+    /// Suffix => analyses it may be stripped from, e.g. "adj" or
+    /// "subst:pl", see dict_lookup::SUFFIX_CONSTRAINTS
+    pub suffix_constraints: BTreeMap<String, Vec<String>>,
+}
+
+fn map_to_owned(map: &phf::Map<&'static str, &'static str>) -> BTreeMap<String, String> {
+    map.entries()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+fn set_to_owned(set: &phf::Set<&'static str>) -> BTreeSet<String> {
+    set.iter().map(|s| s.to_string()).collect()
+}
+
+impl Default for Theory {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl Theory {
+    /// The tables compiled into dict_lookup
+    pub fn builtin() -> Self {
+        Self {
+            left_hand_combos: map_to_owned(&dict_lookup::LEFT_HAND_COMBOS),
+            center_combos: map_to_owned(&dict_lookup::CENTER_COMBOS),
+            right_hand_combos: map_to_owned(&dict_lookup::RIGHT_HAND_COMBOS),
+            prefixes: map_to_owned(&dict_lookup::PREFIXES),
+            suffixes: map_to_owned(&dict_lookup::SUFFIXES),
+            infixes: map_to_owned(&dict_lookup::INFIXES),
+            shortcuts: map_to_owned(&dict_lookup::SHORTCUTS),
+            special_chars: map_to_owned(&dict_lookup::SPECIAL_CHARS),
+            commands: map_to_owned(&dict_lookup::COMMANDS),
+            prefix_exceptions: set_to_owned(&dict_lookup::PREFIX_EXCEPTIONS),
+            suffix_exceptions: set_to_owned(&dict_lookup::SUFFIX_EXCEPTIONS),
+            suffix_constraints: dict_lookup::SUFFIX_CONSTRAINTS
+                .entries()
+                .map(|(k, v)| (k.to_string(), v.iter().map(|c| c.to_string()).collect()))
+                .collect(),
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ErrBox> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        Self::from_json_str(&text, &path.display().to_string())
+    }
+
+    /// Like from_file(), but chords are left unchecked, for lint
+    pub fn from_file_unchecked(path: &Path) -> Result<Self, ErrBox> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        let (theory, _file_sections) = Self::merge_json_str(&text, &path.display().to_string())?;
+
+        Ok(theory)
+    }
+
+    /// Parse and validate a JSON theory. origin is only used for error
+    /// messages.
+    pub fn from_json_str(text: &str, origin: &str) -> Result<Self, ErrBox> {
+        let (theory, file_sections) = Self::merge_json_str(text, origin)?;
+
+        let (errors, warnings): (Vec<_>, Vec<_>) = theory
+            .validate()
+            .into_iter()
+            .partition(|e| e.kind == TheoryErrorKind::Unparseable);
+
+        let describe = |e: &TheoryError| {
+            format!(
+                "{}:{}: {} {:?} => {:?}: {}",
+                origin,
+                locate(text, e.section, Some(&e.key)),
+                e.section,
+                e.key,
+                e.chord,
+                e.message
+            )
+        };
+
+        // Built-in sections are known to carry a few of these, only
+        // complain about the ones coming from the file
+        for warning in warnings
+            .iter()
+            .filter(|w| file_sections.contains(w.section))
+        {
+            warn!("{}", describe(warning));
+        }
+
+        if !errors.is_empty() {
+            let messages: Vec<String> = errors.iter().map(describe).collect();
+
+            return Err(messages.join("\n").into());
+        }
+
+        Ok(theory)
+    }
+
+    /// The built-in theory with the sections of a JSON theory merged
+    /// over it, and the names of those sections
+    fn merge_json_str(text: &str, origin: &str) -> Result<(Self, BTreeSet<String>), ErrBox> {
+        let parsed: Value = serde_json::from_str(text)
+            .map_err(|e| format!("{}:{}:{}: {}", origin, e.line(), e.column(), e))?;
+
+        // Start from the built-in theory and merge file sections over it
+        let mut merged = serde_json::to_value(Self::builtin())?;
+
+        let (Value::Object(sections), Value::Object(merged_sections)) = (parsed, &mut merged)
+        else {
+            return Err(format!("{}:1: theory file must be a JSON object", origin).into());
+        };
+
+        let file_sections: BTreeSet<String> = sections.keys().cloned().collect();
+
+        for (section, contents) in sections {
+            if !merged_sections.contains_key(&section) {
+                return Err(format!(
+                    "{}:{}: unknown section {:?}",
+                    origin,
+                    locate(text, &section, None),
+                    section
+                )
+                .into());
+            }
+            merged_sections.insert(section, contents);
+        }
+
+        let theory: Self = serde_json::from_value(merged)
+            .map_err(|e| format!("{}: malformed theory: {}", origin, e))?;
+
+        Ok((theory, file_sections))
+    }
+
+    pub fn save(&self, f: File) -> Result<(), ErrBox> {
+        serde_json::to_writer_pretty(f, self)?;
+        Ok(())
+    }
+
+    /// Every (section name, table) pair of text => chord tables.
+    /// Right-hand chords are returned as written, without the hyphen.
+    pub fn tables(&self) -> Vec<(&'static str, &BTreeMap<String, String>)> {
+        vec![
+            ("left_hand_combos", &self.left_hand_combos),
+            ("center_combos", &self.center_combos),
+            ("right_hand_combos", &self.right_hand_combos),
+            ("prefixes", &self.prefixes),
+            ("suffixes", &self.suffixes),
+            ("infixes", &self.infixes),
+            ("shortcuts", &self.shortcuts),
+            ("special_chars", &self.special_chars),
+            ("commands", &self.commands),
+        ]
+    }
+
+    /// Check that every chord string parses and avoids invalid key
+    /// combinations. Commands are exempt from the combination check, as
+    /// some Plover commands deliberately use them.
+    pub fn validate(&self) -> Vec<TheoryError> {
+        let mut errors = Vec::new();
+
+        for (section, table) in self.tables() {
+            for (key, chord) in table.iter() {
+                let chord_str = if section == "right_hand_combos" {
+                    format!("-{}", chord)
+                } else {
+                    chord.clone()
+                };
+
+                let error = match chord_str.parse::<Chord>() {
+                    Err(e) => Some((TheoryErrorKind::Unparseable, e)),
+                    Ok(_ch) if section == "commands" => None,
+                    Ok(ch) => ch
+                        .validate()
+                        .err()
+                        .map(|e| (TheoryErrorKind::InvalidCombination, e)),
+                };
+
+                if let Some((kind, e)) = error {
+                    errors.push(TheoryError {
+                        kind,
+                        section,
+                        key: key.clone(),
+                        chord: chord.clone(),
+                        message: e.to_string(),
+                    });
+                }
+            }
+        }
+
+        errors
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TheoryErrorKind {
+    /// Fails Chord::from_str, the theory cannot be used
+    Unparseable,
+    /// Parses, but Chord::validate rejects it
+    InvalidCombination,
+}
+
+#[derive(Clone, Debug)]
+pub struct TheoryError {
+    pub kind: TheoryErrorKind,
+    pub section: &'static str,
+    pub key: String,
+    pub chord: String,
+    pub message: String,
+}
+
+/// Best-effort line number of a section, or of a key within a section,
+/// in the JSON source. Falls back to line 1.
+fn locate(text: &str, section: &str, key: Option<&str>) -> usize {
+    let quoted = |s: &str| serde_json::to_string(s).unwrap_or_default();
+
+    let Some(mut offset) = text.find(&quoted(section)) else {
+        return 1;
+    };
+
+    if let Some(key) = key {
+        match text[offset..].find(&quoted(key)) {
+            Some(key_offset) => offset += key_offset,
+            None => return 1,
+        }
+    }
+
+    text[..offset].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_theory_parses() {
+        let errors: Vec<_> = Theory::builtin()
+            .validate()
+            .into_iter()
+            .filter(|e| e.kind == TheoryErrorKind::Unparseable)
+            .collect();
+
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_suffix_constraints_name_suffixes() {
+        let theory = Theory::builtin();
+
+        for suffix in theory.suffix_constraints.keys() {
+            assert!(theory.suffixes.contains_key(suffix), "{}", suffix);
+        }
+    }
+
+    #[test]
+    fn test_infixes_build_on_null_infix() -> Result<(), ErrBox> {
+        let null_infix: Chord = dict_lookup::NULL_INFIX.parse()?;
+
+        for (infix, chord) in Theory::builtin().infixes.iter() {
+            let chord: Chord = chord.parse()?;
+            assert!(chord.contains(&null_infix), "{:?}", infix);
+            chord.validate()?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_sections_override_builtin() -> Result<(), ErrBox> {
+        let text = r#"{
+            "prefix_exceptions": ["pralina", "przepych"]
+        }"#;
+
+        let theory = Theory::from_json_str(text, "test.json")?;
+
+        assert_eq!(theory.prefix_exceptions.len(), 2);
+        assert_eq!(theory.suffixes, Theory::builtin().suffixes);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_chord_reports_line() {
+        let text = "{\n    \"suffixes\": {\n        \"a\": \"ZKPLA\",\n        \"ach\": \"ZKPLAQ\"\n    }\n}";
+
+        let err = Theory::from_json_str(text, "test.json").unwrap_err();
+
+        assert!(err.to_string().starts_with("test.json:4: suffixes \"ach\""));
+    }
+}
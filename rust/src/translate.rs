@@ -0,0 +1,270 @@
+//! Text-to-steno translation of running text, e.g. for training
+//! material or for checking how much of a passage the theory covers.
+
+use crate::{chord::Chord, generator::Generator, ErrBox};
+
+/// Plover command capitalizing the next word
+pub const CAPITALIZE_NEXT: &str = "{-|}";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Token {
+    Word(String),
+    /// Punctuation and other symbols, longest special char match first
+    Symbol(String),
+    /// Digits, there's no number key in the layout
+    Number(String),
+}
+
+impl Token {
+    pub fn text(&self) -> &str {
+        match self {
+            Self::Word(s) | Self::Symbol(s) | Self::Number(s) => s,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TranslatedToken {
+    pub token: Token,
+    /// Printed strokes, capitalization stroke included
+    pub strokes: Vec<String>,
+    /// Why the token couldn't be translated, strokes are empty then
+    pub error: Option<String>,
+}
+
+/// Split text into words, numbers and symbols. Whitespace only
+/// separates tokens, a hyphen between letters joins a compound word. Symbols are matched against special_chars
+/// (longest first, "{.}"-style entries included) so that e.g. "--"
+/// stays one token.
+pub fn tokenize<'a>(text: &str, special_chars: impl IntoIterator<Item = &'a String>) -> Vec<Token> {
+    let mut symbols: Vec<String> = special_chars
+        .into_iter()
+        .map(|s| symbol_text(s).to_owned())
+        .filter(|s| !s.is_empty())
+        .collect();
+    symbols.sort_by_key(|s| std::cmp::Reverse(s.chars().count()));
+
+    let mut ret = Vec::new();
+    let mut rest = text;
+
+    while let Some(ch) = rest.chars().next() {
+        if ch.is_whitespace() {
+            rest = &rest[ch.len_utf8()..];
+            continue;
+        }
+
+        let (token, len) = if ch.is_alphabetic() {
+            let len = word_len(rest);
+            (Token::Word(rest[..len].to_owned()), len)
+        } else if ch.is_ascii_digit() {
+            let len = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            (Token::Number(rest[..len].to_owned()), len)
+        } else {
+            let len = symbols
+                .iter()
+                .find(|s| rest.starts_with(s.as_str()))
+                .map(|s| s.len())
+                .unwrap_or(ch.len_utf8());
+            (Token::Symbol(rest[..len].to_owned()), len)
+        };
+
+        ret.push(token);
+        rest = &rest[len..];
+    }
+
+    ret
+}
+
+/// Length of the word text starts with. A hyphen between two letters
+/// stays inside it, compound words like "biało-czerwona" have their own
+/// outlines.
+fn word_len(text: &str) -> usize {
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((idx, ch)) = chars.next() {
+        let joins_letters = ch == '-'
+            && chars
+                .peek()
+                .is_some_and(|(_idx, next)| next.is_alphabetic());
+
+        if !ch.is_alphabetic() && !joins_letters {
+            return idx;
+        }
+    }
+
+    text.len()
+}
+
+/// The text a special char entry types, e.g. "." for "{.}". Entries
+/// with other Plover syntax ("{^.^}", "{:stop:...}", "<speaker1>")
+/// yield an empty string, they don't correspond to plain symbols.
+fn symbol_text(entry: &str) -> &str {
+    if entry.starts_with('<') && entry.len() > 1 {
+        return "";
+    }
+
+    match entry.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+        Some(inner) if inner.contains(['^', ':', '&', '|']) => "",
+        Some(inner) => inner,
+        None => entry,
+    }
+}
+
+pub struct Translator<'a> {
+    gen: &'a Generator,
+    capitalize: Option<Chord>,
+}
+
+impl<'a> Translator<'a> {
+    pub fn new(gen: &'a Generator) -> Result<Self, ErrBox> {
+        let capitalize = gen
+            .theory
+            .commands
+            .get(CAPITALIZE_NEXT)
+            .map(|chord| chord.parse())
+            .transpose()?;
+
+        Ok(Self { gen, capitalize })
+    }
+
+    pub fn translate(&self, text: &str) -> Vec<TranslatedToken> {
+        tokenize(text, self.gen.theory.special_chars.keys())
+            .into_iter()
+            .map(|token| {
+                let (strokes, error) = match self.translate_token(&token) {
+                    Ok(strokes) => (strokes, None),
+                    Err(e) => (vec![], Some(e.to_string())),
+                };
+
+                TranslatedToken {
+                    token,
+                    strokes,
+                    error,
+                }
+            })
+            .collect()
+    }
+
+    fn translate_token(&self, token: &Token) -> Result<Vec<String>, ErrBox> {
+        match token {
+            Token::Word(word) => {
+                let mut strokes = Vec::new();
+                let mut chars = word.chars();
+                let first = chars.next().unwrap_or_default();
+
+                if chars.any(|c| c.is_uppercase()) {
+                    return Err(format!(
+                        "{:?} rejected - mixed case words are not supported",
+                        word
+                    )
+                    .into());
+                }
+
+                if first.is_uppercase() {
+                    let cap = self.capitalize.ok_or(format!(
+                        "{:?} rejected - theory has no {} command",
+                        word, CAPITALIZE_NEXT
+                    ))?;
+                    strokes.push(cap.to_string());
+                }
+
+                let outline = self.gen.word_outline(word)?;
+                strokes.extend(outline.collapse().iter().map(|ch| ch.to_string()));
+
+                Ok(strokes)
+            }
+            Token::Symbol(symbol) => {
+                let braced = format!("{{{}}}", symbol);
+                let chord = self
+                    .gen
+                    .theory
+                    .special_chars
+                    .get(symbol)
+                    .or_else(|| self.gen.theory.special_chars.get(&braced))
+                    .ok_or_else(|| format!("{:?} rejected - not in special chars", symbol))?;
+
+                Ok(vec![chord.parse::<Chord>()?.to_string()])
+            }
+            Token::Number(number) => {
+                Err(format!("{:?} rejected - numbers are not supported", number).into())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::theory::Theory;
+
+    #[test]
+    fn test_tokenize() {
+        let specials = vec![
+            "{.}".to_owned(),
+            "{,}".to_owned(),
+            "-".to_owned(),
+            "--".to_owned(),
+            "{^.^}".to_owned(),
+        ];
+
+        assert_eq!(
+            tokenize("Ala ma kota, 2 psy -- i żółwia.", &specials),
+            vec![
+                Token::Word("Ala".to_owned()),
+                Token::Word("ma".to_owned()),
+                Token::Word("kota".to_owned()),
+                Token::Symbol(",".to_owned()),
+                Token::Number("2".to_owned()),
+                Token::Word("psy".to_owned()),
+                Token::Symbol("--".to_owned()),
+                Token::Word("i".to_owned()),
+                Token::Word("żółwia".to_owned()),
+                Token::Symbol(".".to_owned()),
+            ]
+        );
+
+        assert_eq!(
+            tokenize("flaga biało-czerwona - e-mail- -kot", &specials),
+            vec![
+                Token::Word("flaga".to_owned()),
+                Token::Word("biało-czerwona".to_owned()),
+                Token::Symbol("-".to_owned()),
+                Token::Word("e-mail".to_owned()),
+                Token::Symbol("-".to_owned()),
+                Token::Symbol("-".to_owned()),
+                Token::Word("kot".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_translate() -> Result<(), ErrBox> {
+        let gen = Generator::new(Theory::builtin())?;
+        let translator = Translator::new(&gen)?;
+
+        let translated = translator.translate("Kot, 7 NATO");
+
+        assert_eq!(translated[0].strokes, vec!["~O", "KAUT"]);
+        assert_eq!(translated[1].strokes, vec!["V-B"]);
+        assert!(translated[2].error.is_some());
+        assert!(translated[3].error.is_some());
+
+        // Compounds keep their own outline
+        let translated = translator.translate("flaga biało-czerwona");
+        assert_eq!(translated.len(), 2);
+        assert_eq!(translated[1].token.text(), "biało-czerwona");
+        assert_eq!(
+            translated[1].strokes,
+            gen.word_outline("biało-czerwona")?
+                .collapse()
+                .iter()
+                .map(|ch| ch.to_string())
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,276 @@
+//! Round-trip verification: every word is turned into its outline and
+//! translated back with plover_engine. Words which don't come back as
+//! themselves are classified by the most likely cause.
+
+use serde::Serialize;
+
+use std::{collections::BTreeMap, io::Write};
+
+use crate::{
+    chord::{Chord, ChordSeqItem, ChordSequence},
+    generator::{root_only, Generator},
+    plover_engine::{format_translations, StenoDictionary, Translation},
+    ErrBox,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerifyStatus {
+    Ok,
+    /// The generator has no outline for the word
+    Rejected,
+    /// Some strokes of the outline aren't in the dictionaries
+    Untranslated,
+    /// Part of the outline translates to a SHORTCUTS entry
+    ShadowedByShortcut,
+    /// The root outline still belongs to more than one word root
+    Ambiguous,
+    /// Another affix has the same chord and took its dictionary entry
+    AffixCollision,
+    /// A prefix stroke was absorbed by a longer outline, the word may
+    /// belong in PREFIX_EXCEPTIONS
+    PrefixMisSplit,
+    /// Same as PrefixMisSplit for a suffix stroke
+    SuffixMisSplit,
+    /// Translates to something else for any other reason
+    WrongWord,
+}
+
+impl VerifyStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Rejected => "rejected",
+            Self::Untranslated => "untranslated",
+            Self::ShadowedByShortcut => "shadowed_by_shortcut",
+            Self::Ambiguous => "ambiguous",
+            Self::AffixCollision => "affix_collision",
+            Self::PrefixMisSplit => "prefix_mis_split",
+            Self::SuffixMisSplit => "suffix_mis_split",
+            Self::WrongWord => "wrong_word",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct VerifyEntry {
+    pub word: String,
+    /// Empty for rejected words
+    pub outline: String,
+    /// What the outline types, or the rejection reason
+    pub output: String,
+    pub status: VerifyStatus,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct VerifyReport {
+    pub checked: usize,
+    pub counts: BTreeMap<VerifyStatus, usize>,
+    /// Failed words only
+    pub failures: Vec<VerifyEntry>,
+}
+
+impl VerifyReport {
+    pub fn count(&self, status: VerifyStatus) -> usize {
+        self.counts.get(&status).cloned().unwrap_or(0)
+    }
+
+    pub fn save_json(&self, w: impl Write) -> Result<(), ErrBox> {
+        serde_json::to_writer_pretty(w, self)?;
+        Ok(())
+    }
+
+    /// One word,outline,output,status row per failure
+    pub fn save_csv(&self, mut w: impl Write) -> Result<(), ErrBox> {
+        writeln!(w, "word,outline,output,status")?;
+
+        for e in self.failures.iter() {
+            writeln!(
+                w,
+                "{},{},{},{}",
+                csv_field(&e.word),
+                csv_field(&e.outline),
+                csv_field(&e.output),
+                e.status.as_str()
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+/// Check every word against dict, which should hold gen's dictionaries
+pub fn verify_words<'a>(
+    gen: &Generator,
+    dict: &StenoDictionary,
+    words: impl IntoIterator<Item = &'a String>,
+) -> VerifyReport {
+    let mut report = VerifyReport::default();
+
+    for word in words {
+        let entry = verify_word(gen, dict, word);
+
+        report.checked += 1;
+        *report.counts.entry(entry.status).or_insert(0) += 1;
+
+        if entry.status != VerifyStatus::Ok {
+            report.failures.push(entry);
+        }
+    }
+
+    report
+}
+
+pub fn verify_word(gen: &Generator, dict: &StenoDictionary, word: &str) -> VerifyEntry {
+    let outline = match gen.word_outline(word) {
+        Ok(outline) => outline,
+        Err(e) => {
+            return VerifyEntry {
+                word: word.to_owned(),
+                outline: String::new(),
+                output: e.to_string(),
+                status: VerifyStatus::Rejected,
+            }
+        }
+    };
+
+    let translations = dict.translate(&outline.collapse());
+    let output = format_translations(&translations);
+
+    let status = if output == word {
+        VerifyStatus::Ok
+    } else if translations.iter().any(|t| t.text.is_none()) {
+        VerifyStatus::Untranslated
+    } else if translations.iter().any(|t| {
+        let [stroke] = t.strokes[..] else {
+            return false;
+        };
+        t.text.as_ref().is_some_and(|text| {
+            gen.theory
+                .shortcuts
+                .get(text)
+                .is_some_and(|ch| ch.parse().ok() == Some(stroke))
+        })
+    }) {
+        VerifyStatus::ShadowedByShortcut
+    } else if is_ambiguous(gen, &outline) {
+        VerifyStatus::Ambiguous
+    } else if has_affix_collision(dict, &outline) {
+        VerifyStatus::AffixCollision
+    } else if affixes_mis_split(
+        outline.items.iter().map_while(|item| match item {
+            ChordSeqItem::Prefix(_s, chord) => Some(chord),
+            _other => None,
+        }),
+        translations.iter(),
+    ) {
+        VerifyStatus::PrefixMisSplit
+    } else if affixes_mis_split(
+        outline.items.iter().rev().map_while(|item| match item {
+            ChordSeqItem::Suffix(_s, chord) => Some(chord),
+            _other => None,
+        }),
+        translations.iter().rev(),
+    ) {
+        VerifyStatus::SuffixMisSplit
+    } else {
+        VerifyStatus::WrongWord
+    };
+
+    VerifyEntry {
+        word: word.to_owned(),
+        outline: outline.print_chords(),
+        output,
+        status,
+    }
+}
+
+fn is_ambiguous(gen: &Generator, outline: &ChordSequence) -> bool {
+    gen.word_root_conflict_dict
+        .get(&root_only(outline))
+        .is_some_and(|words| words.len() > 1)
+}
+
+fn has_affix_collision(dict: &StenoDictionary, outline: &ChordSequence) -> bool {
+    outline.items.iter().any(|item| {
+        let (expected, chord) = match item {
+            ChordSeqItem::Prefix(s, chord) => (format!("{}{{^}}", s), chord),
+            ChordSeqItem::Suffix(s, chord) => (format!("{{^}}{}", s), chord),
+            ChordSeqItem::Infix(s, chord) => (format!("{{^{}^}}", s), chord),
+            ChordSeqItem::RootChord(_, _) | ChordSeqItem::Disambiguation(_) => return false,
+        };
+
+        dict.get(&[*chord]) != Some(&expected)
+    })
+}
+
+/// Whether some of the stacked affix strokes, listed from the outside
+/// in, weren't translated on their own
+fn affixes_mis_split<'a>(
+    mut affix_chords: impl Iterator<Item = &'a Chord>,
+    mut translations: impl Iterator<Item = &'a Translation>,
+) -> bool {
+    affix_chords.any(|chord| translations.next().map(|t| t.strokes.as_slice()) != Some(&[*chord]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::theory::Theory;
+
+    #[test]
+    fn test_verify_words() -> Result<(), ErrBox> {
+        let mut gen = Generator::new(Theory::builtin())?;
+
+        // cit and tit share an outline and are left unresolved, only
+        // one of them makes it into the dictionary
+        let words: Vec<String> = ["kot", "cit", "tit", "x1"]
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+        for word in words.iter().take(3) {
+            gen.add_word_root(word)?;
+        }
+
+        let dict = StenoDictionary::from_generator(&gen)?;
+        let report = verify_words(&gen, &dict, words.iter());
+
+        assert_eq!(report.checked, 4);
+        assert_eq!(report.count(VerifyStatus::Ok), 2);
+        assert_eq!(report.count(VerifyStatus::Ambiguous), 1);
+        assert_eq!(report.count(VerifyStatus::Rejected), 1);
+
+        // Stacked affixes attach to each other
+        gen.max_prefixes = 2;
+        gen.add_word_root("niedopowiedzenie")?;
+        let dict = StenoDictionary::from_generator(&gen)?;
+        let entry = verify_word(&gen, &dict, "niedopowiedzenie");
+        assert!(entry.outline.starts_with("LRE*/TJ*O/"));
+        assert_eq!(entry.status, VerifyStatus::Ok);
+
+        // Suffixes are attached with {^}, so the orthography rules keep
+        // off roots ending in a vowel
+        for word in ["kawaii", "tangoami"] {
+            gen.add_word_root(word)?;
+            let dict = StenoDictionary::from_generator(&gen)?;
+            let entry = verify_word(&gen, &dict, word);
+            assert_eq!(entry.output, word);
+            assert_eq!(entry.status, VerifyStatus::Ok);
+        }
+
+        let mut csv = Vec::new();
+        report.save_csv(&mut csv)?;
+        assert!(String::from_utf8(csv)?.starts_with("word,outline,output,status\n"));
+
+        Ok(())
+    }
+}
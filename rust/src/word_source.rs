@@ -0,0 +1,102 @@
+//! Word list input. Words can come from an odm-format file (each line
+//! is a comma-separated list of forms of one lexeme), a plain
+//! one-word-per-line list, or stdin in either format.
+
+use std::{
+    fs,
+    io::{self, Read},
+    path::PathBuf,
+};
+
+use crate::{generator::sanitize_word, ErrBox};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WordListFormat {
+    /// SJP.PL odmiany format, e.g. "kot, kota, kotem"
+    Odm,
+    /// One word per line
+    Plain,
+}
+
+#[derive(Clone, Debug)]
+pub enum WordSource {
+    File(PathBuf, WordListFormat),
+    Stdin(WordListFormat),
+    /// The odm.txt copy compiled into the binary
+    #[cfg(feature = "embedded-odm")]
+    Embedded,
+}
+
+impl WordSource {
+    /// Read the source into inflection groups. A plain list yields one
+    /// single-word group per line.
+    pub fn read_groups(&self) -> Result<Vec<Vec<String>>, ErrBox> {
+        match self {
+            Self::File(path, format) => {
+                let text = fs::read_to_string(path)
+                    .map_err(|e| format!("{}: {}", path.display(), e))?;
+                Ok(parse_groups(&text, *format))
+            }
+            Self::Stdin(format) => {
+                let mut text = String::new();
+                io::stdin().read_to_string(&mut text)?;
+                Ok(parse_groups(&text, *format))
+            }
+            #[cfg(feature = "embedded-odm")]
+            Self::Embedded => Ok(parse_groups(
+                crate::dict_lookup::SJP_DICT,
+                WordListFormat::Odm,
+            )),
+        }
+    }
+}
+
+/// Split text into groups of sanitized words. Entries which are not
+/// single words or are shorter than two characters are dropped, and so
+/// are groups left empty.
+pub fn parse_groups(text: &str, format: WordListFormat) -> Vec<Vec<String>> {
+    text.lines()
+        .map(|l| {
+            let words: Vec<&str> = match format {
+                WordListFormat::Odm => l.split(", ").collect(),
+                WordListFormat::Plain => vec![l],
+            };
+
+            words
+                .into_iter()
+                .filter_map(sanitize_entry)
+                .collect::<Vec<_>>()
+        })
+        .filter(|group| !group.is_empty())
+        .collect()
+}
+
+/// Ascii alphabet + PL accents only, no multi-word or single character
+/// entries
+pub fn sanitize_entry(word: &str) -> Option<String> {
+    sanitize_word(word)
+        .ok()
+        .filter(|sanitized| sanitized.chars().count() >= 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_groups() {
+        let odm = "kot, kota, kotem\nNowy Jork, Nowego Jorku\na\n";
+
+        assert_eq!(
+            parse_groups(odm, WordListFormat::Odm),
+            vec![vec!["kot", "kota", "kotem"]]
+        );
+
+        let plain = "Kot\npies, kot\n\nżółw\n";
+
+        assert_eq!(
+            parse_groups(plain, WordListFormat::Plain),
+            vec![vec!["kot"], vec!["żółw"]]
+        );
+    }
+}